@@ -0,0 +1,103 @@
+use crate::{Edit, PieceTable};
+use automerge::transaction::Transactable;
+use automerge::{AutoCommit, AutomergeError, ObjId, ObjType, PatchAction, ReadDoc, TextEncoding, ROOT};
+
+pub struct AutomergeText {
+    doc: AutoCommit,
+    text: ObjId,
+}
+
+impl AutomergeText {
+    pub fn new(name: &str) -> Result<Self, AutomergeError> {
+        let mut doc = AutoCommit::new_with_encoding(TextEncoding::Utf8CodeUnit);
+        let text = doc.put_object(ROOT, name, ObjType::Text)?;
+        doc.update_diff_cursor();
+        Ok(AutomergeText { doc, text })
+    }
+
+    pub fn load(data: &[u8], name: &str) -> Result<Self, AutomergeError> {
+        let mut doc = AutoCommit::load_with_options(data, automerge::LoadOptions::new().text_encoding(TextEncoding::Utf8CodeUnit))?;
+        let text = match doc.get(ROOT, name)? {
+            Some((_, text)) => text,
+            None => doc.put_object(ROOT, name, ObjType::Text)?,
+        };
+        doc.update_diff_cursor();
+        Ok(AutomergeText { doc, text })
+    }
+
+    pub fn doc(&mut self) -> &mut AutoCommit {
+        &mut self.doc
+    }
+
+    pub fn table(&self) -> Result<PieceTable, AutomergeError> {
+        Ok(PieceTable::new(self.doc.text(&self.text)?))
+    }
+
+    pub fn apply_update(&mut self, table: &mut PieceTable, update: &[u8]) -> Result<(), AutomergeError> {
+        self.doc.load_incremental(update)?;
+
+        let patches = self.doc.diff_incremental();
+        table.transaction(|table| {
+            for patch in patches.into_iter().filter(|patch| patch.obj == self.text) {
+                match patch.action {
+                    PatchAction::SpliceText { index, value, .. } => table.insert(index, &value.make_string()),
+                    PatchAction::DeleteSeq { index, length } => table.delete(index, length),
+                    _ => {}
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub fn apply_local(&mut self, table: &mut PieceTable, edit: &Edit) -> Result<Vec<u8>, AutomergeError> {
+        table.apply_edit(edit);
+        match edit {
+            Edit::Insert { offset, text } => self.doc.splice_text(&self.text, *offset, 0, text)?,
+            Edit::Delete { range } => self.doc.splice_text(&self.text, range.start, range.len() as isize, "")?,
+            Edit::Replace { range, text } => self.doc.splice_text(&self.text, range.start, range.len() as isize, text)?,
+        }
+        self.doc.update_diff_cursor();
+        Ok(self.doc.save_incremental())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_automerge_round_trip() {
+        let mut local = AutomergeText::new("content").unwrap();
+        let mut local_table = local.table().unwrap();
+        let snapshot = local.doc().save();
+
+        let mut remote = AutomergeText::load(&snapshot, "content").unwrap();
+        let mut remote_table = remote.table().unwrap();
+
+        let first = local
+            .apply_local(
+                &mut local_table,
+                &Edit::Insert {
+                    offset: 0,
+                    text: "hello wörld".to_string(),
+                },
+            )
+            .unwrap();
+        let second = local.apply_local(&mut local_table, &Edit::Delete { range: 0..6 }).unwrap();
+        remote.apply_update(&mut remote_table, &first).unwrap();
+        remote.apply_update(&mut remote_table, &second).unwrap();
+        assert_eq!("wörld", remote_table.text());
+
+        let reply = remote
+            .apply_local(
+                &mut remote_table,
+                &Edit::Insert {
+                    offset: 6,
+                    text: "!".to_string(),
+                },
+            )
+            .unwrap();
+        local.apply_update(&mut local_table, &reply).unwrap();
+        assert_eq!("wörld!", local_table.text());
+    }
+}
@@ -0,0 +1,63 @@
+use crate::PieceTable;
+
+const PAIRS: [(u8, u8); 3] = [(b'(', b')'), (b'[', b']'), (b'{', b'}')];
+
+impl PieceTable {
+    /// Returns the offset of the bracket matching the one at `offset`, if that is one of `()[]{}`.
+    /// Only brackets of the same kind nest, and the scan runs over the chunks without copying.
+    pub fn matching_delimiter(&self, offset: usize) -> Option<usize> {
+        let byte = *self.chunks_in_range(offset..offset.saturating_add(1)).next()?.as_bytes().first()?;
+        let mut depth = 0usize;
+        let mut track = |found: u8, nested: u8, target: u8| {
+            if found == nested {
+                depth += 1;
+            } else if found == target {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            false
+        };
+
+        if let Some(&(open, close)) = PAIRS.iter().find(|(open, _)| *open == byte) {
+            let bytes = self.chunks_in_range(offset + 1..).flat_map(str::bytes);
+            (offset + 1..)
+                .zip(bytes)
+                .find(|&(_, found)| track(found, open, close))
+                .map(|(index, _)| index)
+        } else if let Some(&(open, close)) = PAIRS.iter().find(|(_, close)| *close == byte) {
+            let bytes = self.chunks_in_range(..offset).rev().flat_map(|chunk| chunk.bytes().rev());
+            (0..offset)
+                .rev()
+                .zip(bytes)
+                .find(|&(_, found)| track(found, close, open))
+                .map(|(index, _)| index)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_delimiter() {
+        let mut piece_table = PieceTable::from("fn f(a: [u8; 2]) { g(h(a)) }");
+        piece_table.insert(21, "(x)");
+        assert_eq!("fn f(a: [u8; 2]) { g((x)h(a)) }", piece_table.text());
+
+        assert_eq!(Some(15), piece_table.matching_delimiter(4));
+        assert_eq!(Some(4), piece_table.matching_delimiter(15));
+        assert_eq!(Some(14), piece_table.matching_delimiter(8));
+        assert_eq!(Some(28), piece_table.matching_delimiter(20));
+        assert_eq!(Some(20), piece_table.matching_delimiter(28));
+        assert_eq!(Some(30), piece_table.matching_delimiter(17));
+        assert_eq!(None, piece_table.matching_delimiter(0));
+        assert_eq!(None, piece_table.matching_delimiter(31));
+        assert_eq!(None, piece_table.matching_delimiter(usize::MAX));
+        assert_eq!(None, PieceTable::from("(()").matching_delimiter(0));
+    }
+}
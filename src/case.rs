@@ -0,0 +1,70 @@
+use crate::PieceTable;
+use std::ops::{Range, RangeBounds};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaseTransform {
+    Upper,
+    Lower,
+    Title,
+}
+
+impl CaseTransform {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            CaseTransform::Upper => text.to_uppercase(),
+            CaseTransform::Lower => text.to_lowercase(),
+            CaseTransform::Title => {
+                let mut result = String::with_capacity(text.len());
+                let mut in_word = false;
+                for ch in text.chars() {
+                    if !ch.is_alphanumeric() {
+                        result.push(ch);
+                    } else if in_word {
+                        result.extend(ch.to_lowercase());
+                    } else {
+                        result.extend(ch.to_uppercase());
+                    }
+                    in_word = ch.is_alphanumeric() || (in_word && ch == '\'');
+                }
+                result
+            }
+        }
+    }
+}
+
+impl PieceTable {
+    /// Rewrites the case of `range` as one replacement, so the whole change undoes in one step.
+    /// Nothing is recorded when the text is already in the requested case.
+    pub fn transform_range(&mut self, range: impl RangeBounds<usize>, transform: CaseTransform) {
+        let Range { start, end } = self.resolve_range(range);
+        let text = self.slice_cow(start..end);
+        let transformed = transform.apply(&text);
+        if transformed != text {
+            self.replace(start..end, &transformed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform_range() {
+        let mut piece_table = PieceTable::from("the QUICK brown");
+        piece_table.insert(15, " fox's straße");
+
+        piece_table.transform_range(4.., CaseTransform::Title);
+        assert_eq!("the Quick Brown Fox's Straße", piece_table.text());
+        piece_table.transform_range(..9, CaseTransform::Upper);
+        assert_eq!("THE QUICK Brown Fox's Straße", piece_table.text());
+        piece_table.transform_range(22.., CaseTransform::Upper);
+        assert_eq!("THE QUICK Brown Fox's STRASSE", piece_table.text());
+
+        piece_table.transform_range(..3, CaseTransform::Upper);
+        assert_eq!(4, piece_table.history_len());
+        assert!(piece_table.undo());
+        assert_eq!("THE QUICK Brown Fox's Straße", piece_table.text());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::PieceTable;
+use std::ops::{Bound, RangeBounds};
+
+impl PieceTable {
+    pub fn len_chars(&self) -> usize {
+        self.pieces.iter().map(|piece| self.piece_text(piece).chars().count()).sum()
+    }
+
+    pub fn count_chars_in(&self, range: impl RangeBounds<usize>) -> usize {
+        self.chunks_in_range(range)
+            .map(|chunk| chunk.bytes().filter(|&byte| is_char_start(byte)).count())
+            .sum()
+    }
+
+    /// Counts occurrences of `ch`. Line breaks are already indexed per piece, so counting `'\n'`
+    /// never looks at the text.
+    pub fn count_char(&self, ch: char) -> usize {
+        if ch == '\n' {
+            return self.line_count() - 1;
+        }
+        let mut encoded = [0; 4];
+        let needle = ch.encode_utf8(&mut encoded);
+        self.chunks().map(|chunk| count_in(chunk, needle)).sum()
+    }
+
+    pub fn char_idx_to_byte(&self, char_idx: usize) -> usize {
+        let mut remaining = char_idx;
+        let mut running_total = 0;
+        for piece in &self.pieces {
+            let text = self.piece_text(piece);
+            if let Some((index, _)) = text.char_indices().nth(remaining) {
+                return running_total + index;
+            }
+            remaining -= text.chars().count();
+            running_total += piece.length;
+        }
+        running_total
+    }
+
+    pub fn byte_to_char_idx(&self, offset: usize) -> usize {
+        let mut char_idx = 0;
+        let mut running_total = 0;
+        for piece in &self.pieces {
+            let text = self.piece_text(piece);
+            let local = offset.saturating_sub(running_total).min(piece.length);
+            char_idx += text.as_bytes()[..local].iter().filter(|&&byte| is_char_start(byte)).count();
+            if local < piece.length {
+                if !text.is_char_boundary(local) {
+                    char_idx -= 1;
+                }
+                break;
+            }
+            running_total += piece.length;
+        }
+        char_idx
+    }
+
+    pub fn is_char_boundary(&self, offset: usize) -> bool {
+        match self.piece_at(offset) {
+            Some((piece, _, running_total)) => self.piece_text(&piece).is_char_boundary(offset - running_total),
+            None => offset == self.length(),
+        }
+    }
+
+    pub fn floor_char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.length());
+        while !self.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+
+    pub fn ceil_char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.length());
+        while !self.is_char_boundary(offset) {
+            offset += 1;
+        }
+        offset
+    }
+
+    pub fn prev_char_offset(&self, offset: usize) -> Option<usize> {
+        let offset = self.ceil_char_boundary(offset);
+        (offset > 0).then(|| self.floor_char_boundary(offset - 1))
+    }
+
+    pub fn next_char_offset(&self, offset: usize) -> Option<usize> {
+        let offset = self.floor_char_boundary(offset);
+        (offset < self.length()).then(|| self.ceil_char_boundary(offset + 1))
+    }
+
+    pub fn insert_char_idx(&mut self, char_idx: usize, content: &str) {
+        let offset = self.char_idx_to_byte(char_idx);
+        self.insert(offset, content);
+    }
+
+    pub fn delete_char_range(&mut self, range: impl RangeBounds<usize>) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let start = self.char_idx_to_byte(start);
+        let end = match range.end_bound() {
+            Bound::Included(&end) => self.char_idx_to_byte(end.saturating_add(1)),
+            Bound::Excluded(&end) => self.char_idx_to_byte(end),
+            Bound::Unbounded => self.length(),
+        };
+        self.delete(start, end.saturating_sub(start));
+    }
+}
+
+fn is_char_start(byte: u8) -> bool {
+    byte & 0xC0 != 0x80
+}
+
+// Pieces never split a char, so a char is found whole inside a single chunk.
+#[cfg(feature = "memchr")]
+fn count_in(chunk: &str, needle: &str) -> usize {
+    match needle.as_bytes() {
+        &[byte] => memchr::memchr_iter(byte, chunk.as_bytes()).count(),
+        bytes => memchr::memmem::find_iter(chunk.as_bytes(), bytes).count(),
+    }
+}
+
+#[cfg(not(feature = "memchr"))]
+fn count_in(chunk: &str, needle: &str) -> usize {
+    chunk.matches(needle).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_indices() {
+        let mut piece_table = PieceTable::from("naïve");
+        piece_table.insert(6, " café");
+        assert_eq!("naïve café", piece_table.text());
+        assert_eq!(10, piece_table.len_chars());
+
+        assert_eq!(4, piece_table.char_idx_to_byte(3));
+        assert_eq!(10, piece_table.char_idx_to_byte(9));
+        assert_eq!(12, piece_table.char_idx_to_byte(100));
+        assert_eq!(3, piece_table.byte_to_char_idx(4));
+        assert_eq!(9, piece_table.byte_to_char_idx(11));
+        assert_eq!(10, piece_table.byte_to_char_idx(100));
+        assert_eq!(Some('é'), piece_table.char_at(10));
+
+        piece_table.insert_char_idx(9, "—");
+        assert_eq!("naïve caf—é", piece_table.text());
+        piece_table.delete_char_range(2..=4);
+        piece_table.delete_char_range(7..);
+        assert_eq!("na caf—", piece_table.text());
+    }
+
+    #[test]
+    fn test_count_char() {
+        let mut piece_table = PieceTable::from("a€b\nc€\n");
+        piece_table.insert(5, "€€\n");
+        assert_eq!("a€b€€\n\nc€\n", piece_table.text());
+        assert_eq!(4, piece_table.count_char('€'));
+        assert_eq!(3, piece_table.count_char('\n'));
+        assert_eq!(1, piece_table.count_char('b'));
+        assert_eq!(0, piece_table.count_char('x'));
+    }
+
+    #[test]
+    fn test_count_chars_in() {
+        let mut piece_table = PieceTable::from("añb€c");
+        piece_table.insert(3, "xy");
+        assert_eq!("añxyb€c", piece_table.text());
+        assert_eq!(7, piece_table.count_chars_in(..));
+        assert_eq!(3, piece_table.count_chars_in(1..5));
+        assert_eq!(2, piece_table.count_chars_in(6..10));
+        assert_eq!(0, piece_table.count_chars_in(4..4));
+    }
+
+    #[test]
+    fn test_char_boundaries() {
+        let mut piece_table = PieceTable::from("a🦀");
+        piece_table.insert(5, "é");
+        assert!(piece_table.is_char_boundary(1));
+        assert!(!piece_table.is_char_boundary(3));
+        assert!(piece_table.is_char_boundary(7));
+        assert!(!piece_table.is_char_boundary(8));
+
+        assert_eq!(1, piece_table.floor_char_boundary(4));
+        assert_eq!(5, piece_table.ceil_char_boundary(2));
+        assert_eq!(7, piece_table.ceil_char_boundary(100));
+        assert_eq!(Some(5), piece_table.next_char_offset(1));
+        assert_eq!(Some(7), piece_table.next_char_offset(6));
+        assert_eq!(None, piece_table.next_char_offset(7));
+        assert_eq!(Some(5), piece_table.prev_char_offset(7));
+        assert_eq!(Some(1), piece_table.prev_char_offset(5));
+        assert_eq!(None, piece_table.prev_char_offset(0));
+    }
+
+    #[test]
+    fn test_edits_round_to_char_boundaries() {
+        let mut piece_table = PieceTable::from("aé");
+        piece_table.insert(2, "b");
+        assert_eq!("abé", piece_table.text());
+
+        piece_table.delete(3, 1);
+        assert_eq!("ab", piece_table.text());
+        piece_table.insert(2, "€");
+        piece_table.replace(3..4, "c");
+        assert_eq!("abc", piece_table.text());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::PieceTable;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CharId {
+    pub counter: u64,
+    pub site: u32,
+}
+
+impl CharId {
+    fn plus(self, delta: usize) -> CharId {
+        CharId {
+            counter: self.counter + delta as u64,
+            site: self.site,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CrdtOp {
+    Insert { id: CharId, origin: Option<CharId>, text: String },
+    Delete { id: CharId, length: usize },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Run {
+    id: CharId,
+    length: usize,
+    deleted: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrdtTable {
+    table: PieceTable,
+    site: u32,
+    clock: u64,
+    runs: Vec<Run>,
+}
+
+impl CrdtTable {
+    pub const ORIGINAL_SITE: u32 = 0;
+
+    pub fn new(original: String, site: u32) -> Self {
+        assert_ne!(site, Self::ORIGINAL_SITE, "site 0 is reserved for the original text");
+
+        let mut runs = Vec::new();
+        if !original.is_empty() {
+            runs.push(Run {
+                id: CharId {
+                    counter: 0,
+                    site: Self::ORIGINAL_SITE,
+                },
+                length: original.len(),
+                deleted: false,
+            });
+        }
+        CrdtTable {
+            clock: original.len() as u64,
+            table: PieceTable::new(original),
+            site,
+            runs,
+        }
+    }
+
+    pub fn table(&self) -> &PieceTable {
+        &self.table
+    }
+
+    pub fn text(&self) -> String {
+        self.table.text()
+    }
+
+    pub fn insert(&mut self, offset: usize, text: &str) -> Option<CrdtOp> {
+        if text.is_empty() {
+            return None;
+        }
+
+        let offset = self.table.floor_char_boundary(offset);
+        let origin = offset.checked_sub(1).and_then(|previous| self.id_at(previous));
+        let id = CharId {
+            counter: self.clock,
+            site: self.site,
+        };
+        let op = CrdtOp::Insert {
+            id,
+            origin,
+            text: text.to_string(),
+        };
+        self.apply(&op);
+        Some(op)
+    }
+
+    pub fn delete(&mut self, offset: usize, length: usize) -> Vec<CrdtOp> {
+        // Rounded out to whole chars like `PieceTable::delete`, so the ops match what it removes.
+        let end = self.table.ceil_char_boundary(offset.saturating_add(length));
+        let offset = self.table.floor_char_boundary(offset);
+        let mut ops = Vec::new();
+        let mut position = 0;
+        for run in self.runs.iter().filter(|run| !run.deleted) {
+            let start = offset.max(position);
+            let end = end.min(position + run.length);
+            if start < end {
+                ops.push(CrdtOp::Delete {
+                    id: run.id.plus(start - position),
+                    length: end - start,
+                });
+            }
+            position += run.length;
+        }
+
+        for op in &ops {
+            self.apply(op);
+        }
+        ops
+    }
+
+    pub fn apply(&mut self, op: &CrdtOp) -> bool {
+        match op {
+            CrdtOp::Insert { id, origin, text } => self.integrate_insert(*id, *origin, text),
+            CrdtOp::Delete { id, length } => self.integrate_delete(*id, *length),
+        }
+    }
+
+    pub fn id_at(&self, offset: usize) -> Option<CharId> {
+        let mut position = 0;
+        for run in self.runs.iter().filter(|run| !run.deleted) {
+            if offset < position + run.length {
+                return Some(run.id.plus(offset - position));
+            }
+            position += run.length;
+        }
+        None
+    }
+
+    pub fn offset_of(&self, id: CharId) -> Option<usize> {
+        let (index, within) = self.locate(id)?;
+        let run = self.runs[index];
+        let within = if run.deleted { 0 } else { within };
+        Some(self.visible_offset(index) + within)
+    }
+
+    fn integrate_insert(&mut self, id: CharId, origin: Option<CharId>, text: &str) -> bool {
+        if self.locate(id).is_some() {
+            return true;
+        }
+        let mut index = match origin {
+            None => 0,
+            Some(origin) => match self.locate(origin) {
+                Some((index, within)) => {
+                    self.split(index, within + 1);
+                    index + 1
+                }
+                None => return false,
+            },
+        };
+        while index < self.runs.len() && self.runs[index].id > id {
+            index += 1;
+        }
+
+        let offset = self.visible_offset(index);
+        self.table.insert(offset, text);
+        self.runs.insert(
+            index,
+            Run {
+                id,
+                length: text.len(),
+                deleted: false,
+            },
+        );
+        self.clock = self.clock.max(id.counter + text.len() as u64);
+        true
+    }
+
+    fn integrate_delete(&mut self, id: CharId, length: usize) -> bool {
+        let mut remaining = length;
+        let mut next = id;
+        while remaining > 0 {
+            let Some((index, within)) = self.locate(next) else {
+                return false;
+            };
+            self.split(index, within);
+            let index = if within > 0 { index + 1 } else { index };
+            let taken = remaining.min(self.runs[index].length);
+            self.split(index, taken);
+
+            if !self.runs[index].deleted {
+                let offset = self.visible_offset(index);
+                self.table.delete(offset, taken);
+                self.runs[index].deleted = true;
+            }
+            next = next.plus(taken);
+            remaining -= taken;
+        }
+        true
+    }
+
+    fn locate(&self, id: CharId) -> Option<(usize, usize)> {
+        self.runs.iter().enumerate().find_map(|(index, run)| {
+            let starts_before = run.id.counter <= id.counter;
+            let contains = run.id.site == id.site && starts_before && id.counter < run.id.counter + run.length as u64;
+            contains.then(|| (index, (id.counter - run.id.counter) as usize))
+        })
+    }
+
+    fn split(&mut self, index: usize, at: usize) {
+        let run = self.runs[index];
+        if at == 0 || at >= run.length {
+            return;
+        }
+        self.runs[index].length = at;
+        self.runs.insert(
+            index + 1,
+            Run {
+                id: run.id.plus(at),
+                length: run.length - at,
+                deleted: run.deleted,
+            },
+        );
+    }
+
+    fn visible_offset(&self, index: usize) -> usize {
+        self.runs[..index].iter().filter(|run| !run.deleted).map(|run| run.length).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_edits_converge() {
+        let mut alice = CrdtTable::new("hello world".to_string(), 1);
+        let mut bob = CrdtTable::new("hello world".to_string(), 2);
+
+        let marker = alice.id_at(6).unwrap();
+        let mut from_alice = vec![alice.insert(5, ",").unwrap(), alice.insert(12, "!").unwrap()];
+        from_alice.extend(alice.delete(0, 1));
+        let mut from_bob = vec![bob.insert(5, " there").unwrap(), bob.insert(11, ",").unwrap()];
+        from_bob.extend(bob.delete(2, 5));
+
+        for op in &from_bob {
+            assert!(alice.apply(op));
+        }
+        for op in &from_alice {
+            assert!(bob.apply(op));
+        }
+
+        assert_eq!(alice.text(), bob.text());
+        assert_eq!("ehere,, world!", alice.text());
+        assert_eq!(Some(8), alice.offset_of(marker));
+        assert_eq!(alice.offset_of(marker), bob.offset_of(marker));
+    }
+
+    #[test]
+    fn test_edits_round_to_char_boundaries() {
+        let mut table = CrdtTable::new("é".to_string(), 1);
+        table.insert(1, "x");
+        assert_eq!("xé", table.text());
+        assert_eq!(Some(0), table.offset_of(table.id_at(0).unwrap()));
+
+        let ops = table.delete(2, 1);
+        assert_eq!("x", table.text());
+        assert_eq!(
+            vec![CrdtOp::Delete {
+                id: CharId {
+                    counter: 0,
+                    site: CrdtTable::ORIGINAL_SITE
+                },
+                length: 2,
+            }],
+            ops
+        );
+        assert_eq!(1, table.delete(0, usize::MAX).len());
+        assert_eq!("", table.text());
+    }
+}
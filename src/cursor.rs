@@ -0,0 +1,186 @@
+use crate::PieceTable;
+
+/// An editing position that remembers which piece it is in. Stepping only walks the pieces between
+/// the old and new positions, and edits at the cursor splice from the remembered piece instead of
+/// looking it up again.
+#[derive(Debug)]
+pub struct Cursor<'a> {
+    table: &'a mut PieceTable,
+    offset: usize,
+    index: usize,
+    piece_start: usize,
+}
+
+impl Cursor<'_> {
+    pub fn table(&self) -> &PieceTable {
+        self.table
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn seek(&mut self, offset: usize) {
+        let (index, piece_start) = self.locate(offset);
+        let mut offset = if index == self.table.pieces.len() { piece_start } else { offset };
+        while !self.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        self.offset = offset;
+    }
+
+    pub fn peek_char(&mut self) -> Option<char> {
+        let (index, piece_start) = self.locate(self.offset);
+        let piece = self.table.pieces.get(index)?;
+        self.table.piece_text(piece)[self.offset - piece_start..].chars().next()
+    }
+
+    pub fn next_char(&mut self) -> Option<char> {
+        let ch = self.peek_char()?;
+        self.offset += ch.len_utf8();
+        Some(ch)
+    }
+
+    pub fn prev_char(&mut self) -> Option<char> {
+        let ch = self.peek_prev_char()?;
+        self.offset -= ch.len_utf8();
+        Some(ch)
+    }
+
+    pub fn insert_here(&mut self, content: &str) {
+        if content.is_empty() {
+            return;
+        }
+        self.anchor();
+        let content = self.table.normalize_insert(content).into_owned();
+        let add_piece = self.table.push_add(&content);
+        let change = self
+            .table
+            .replace_pieces_at((self.index, self.piece_start), self.offset, 0, vec![add_piece]);
+        self.table.record_insert(change, &content);
+        self.offset += content.len();
+    }
+
+    pub fn delete_forward(&mut self) -> Option<char> {
+        let ch = self.peek_char()?;
+        self.delete_here(ch.len_utf8());
+        Some(ch)
+    }
+
+    pub fn delete_backward(&mut self) -> Option<char> {
+        let ch = self.prev_char()?;
+        self.delete_here(ch.len_utf8());
+        Some(ch)
+    }
+
+    fn delete_here(&mut self, length: usize) {
+        self.anchor();
+        let change = self
+            .table
+            .replace_pieces_at((self.index, self.piece_start), self.offset, length, Vec::new());
+        self.table.history.record(change);
+    }
+
+    fn peek_prev_char(&mut self) -> Option<char> {
+        let (index, piece_start) = self.locate(self.offset.checked_sub(1)?);
+        let piece = self.table.pieces.get(index)?;
+        self.table.piece_text(piece)[..self.offset - piece_start].chars().next_back()
+    }
+
+    fn is_char_boundary(&mut self, offset: usize) -> bool {
+        let (index, piece_start) = self.locate(offset);
+        match self.table.pieces.get(index) {
+            Some(piece) => self.table.piece_text(piece).is_char_boundary(offset - piece_start),
+            None => true,
+        }
+    }
+
+    /// Moves the remembered piece in front of the cursor, where edits splice from. An edit at the
+    /// cursor leaves every piece that starts before it in place, so the piece stays valid across
+    /// the edit.
+    fn anchor(&mut self) {
+        if let Some(before) = self.offset.checked_sub(1) {
+            self.locate(before);
+        } else {
+            self.index = 0;
+            self.piece_start = 0;
+        }
+    }
+
+    fn locate(&mut self, offset: usize) -> (usize, usize) {
+        let pieces = &self.table.pieces;
+        while self.index > 0 && (self.index >= pieces.len() || offset < self.piece_start) {
+            self.index -= 1;
+            self.piece_start -= pieces[self.index].length;
+        }
+        while self.index < pieces.len() && offset >= self.piece_start + pieces[self.index].length {
+            self.piece_start += pieces[self.index].length;
+            self.index += 1;
+        }
+        (self.index, self.piece_start)
+    }
+}
+
+impl PieceTable {
+    pub fn cursor(&mut self, offset: usize) -> Cursor<'_> {
+        let offset = self.floor_char_boundary(offset);
+        Cursor {
+            table: self,
+            offset,
+            index: 0,
+            piece_start: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let mut piece_table = PieceTable::from("añb");
+        piece_table.push_str("çd");
+
+        let mut cursor = piece_table.cursor(2);
+        assert_eq!(1, cursor.offset());
+        assert_eq!(Some('ñ'), cursor.next_char());
+        assert_eq!(Some('b'), cursor.next_char());
+        cursor.insert_here("XY");
+        assert_eq!(Some('ç'), cursor.delete_forward());
+        assert_eq!(Some('Y'), cursor.delete_backward());
+        assert_eq!(Some('X'), cursor.prev_char());
+        assert_eq!(Some('b'), cursor.prev_char());
+
+        cursor.seek(100);
+        assert_eq!(6, cursor.offset());
+        assert_eq!(None, cursor.next_char());
+        assert_eq!(Some('d'), cursor.delete_backward());
+        cursor.seek(2);
+        assert_eq!(1, cursor.offset());
+        assert_eq!("añbX", cursor.table().text());
+
+        piece_table.undo();
+        assert_eq!("añbXd", piece_table.text());
+    }
+
+    #[test]
+    fn test_cursor_edits_from_its_piece() {
+        let mut piece_table = PieceTable::from("abc");
+        piece_table.insert(1, "1");
+        piece_table.insert(3, "2");
+        assert_eq!(5, piece_table.pieces.len());
+
+        let mut cursor = piece_table.cursor(0);
+        cursor.seek(4);
+        // Looking the piece up from these start offsets would land in the wrong place; the cursor
+        // splices from the piece it remembers, and the edit brings the offsets up to date.
+        cursor.table.piece_starts[2..].fill(0);
+        cursor.insert_here("X");
+        assert_eq!(Some('X'), cursor.delete_backward());
+        cursor.table.piece_starts[2..].fill(0);
+        assert_eq!(Some('c'), cursor.delete_forward());
+        assert_eq!("a1b2", cursor.table().text());
+        assert_eq!(vec![0, 1, 2, 3], piece_table.piece_starts);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::merge::hunks;
+use crate::{Edit, PieceTable};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DeltaOp {
+    Insert(String),
+    Retain(usize),
+    Delete(usize),
+}
+
+impl PieceTable {
+    pub fn from_delta(delta: &[DeltaOp]) -> PieceTable {
+        let mut original = String::new();
+        for op in delta {
+            if let DeltaOp::Insert(text) = op {
+                original.push_str(text);
+            }
+        }
+        PieceTable::new(original)
+    }
+
+    pub fn to_delta(&self) -> Vec<DeltaOp> {
+        match self.length() {
+            0 => Vec::new(),
+            _ => vec![DeltaOp::Insert(self.text())],
+        }
+    }
+
+    pub fn apply_delta(&mut self, delta: &[DeltaOp]) {
+        let edits = self.delta_to_edits(delta);
+        self.transaction(|table| {
+            for edit in &edits {
+                table.apply_edit(edit);
+            }
+        });
+    }
+
+    pub fn delta_to_edits(&self, delta: &[DeltaOp]) -> Vec<Edit> {
+        let text = self.text();
+        let (mut source, mut position) = (0, 0);
+        let mut edits = Vec::new();
+
+        for op in delta {
+            match op {
+                DeltaOp::Retain(units) => {
+                    let length = utf16_prefix_len(&text[source..], *units);
+                    source += length;
+                    position += length;
+                }
+                DeltaOp::Delete(units) => {
+                    let length = utf16_prefix_len(&text[source..], *units);
+                    source += length;
+                    edits.push(Edit::Delete {
+                        range: position..position + length,
+                    });
+                }
+                DeltaOp::Insert(inserted) => {
+                    edits.push(Edit::Insert {
+                        offset: position,
+                        text: inserted.clone(),
+                    });
+                    position += inserted.len();
+                }
+            }
+        }
+        edits
+    }
+
+    pub fn edits_to_delta(&self, edits: &[Edit]) -> Vec<DeltaOp> {
+        let mut edited = self.fork();
+        for edit in edits {
+            edited.apply_edit(edit);
+        }
+
+        let text = self.text();
+        let mut delta = Vec::new();
+        let mut source = 0;
+        for hunk in hunks(&self.snapshot().diff(&edited.snapshot())) {
+            if hunk.range.start > source {
+                delta.push(DeltaOp::Retain(utf16_len(&text[source..hunk.range.start])));
+            }
+            if !hunk.text.is_empty() {
+                delta.push(DeltaOp::Insert(hunk.text));
+            }
+            if !hunk.range.is_empty() {
+                delta.push(DeltaOp::Delete(utf16_len(&text[hunk.range.clone()])));
+            }
+            source = hunk.range.end;
+        }
+        delta
+    }
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+fn utf16_prefix_len(text: &str, units: usize) -> usize {
+    let mut counted = 0;
+    for (index, ch) in text.char_indices() {
+        if counted >= units {
+            return index;
+        }
+        counted += ch.len_utf16();
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_round_trip() {
+        let mut piece_table = PieceTable::from_delta(&[DeltaOp::Insert("Gandalf".to_string()), DeltaOp::Insert(" the Grey 🧙".to_string())]);
+        assert_eq!(vec![DeltaOp::Insert("Gandalf the Grey 🧙".to_string())], piece_table.to_delta());
+
+        let delta = vec![
+            DeltaOp::Retain(12),
+            DeltaOp::Insert("White".to_string()),
+            DeltaOp::Delete(4),
+            DeltaOp::Retain(1),
+            DeltaOp::Insert("!".to_string()),
+            DeltaOp::Delete(2),
+        ];
+        assert_eq!(delta, piece_table.edits_to_delta(&piece_table.delta_to_edits(&delta)));
+
+        piece_table.apply_delta(&delta);
+        assert_eq!("Gandalf the White !", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("Gandalf the Grey 🧙", piece_table.text());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_delta_json() {
+        let delta = vec![DeltaOp::Retain(3), DeltaOp::Insert("a".to_string()), DeltaOp::Delete(1)];
+        let json = serde_json::to_string(&delta).unwrap();
+        assert_eq!(r#"[{"retain":3},{"insert":"a"},{"delete":1}]"#, json);
+    }
+}
@@ -0,0 +1,475 @@
+use crate::{Buffer, Edit, PieceTable, Snapshot};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Finds a shortest edit script with Myers' linear-space refinement: instead of keeping every
+/// round's furthest reaching paths, it searches from both ends for the middle of an optimal path
+/// and recurses on the halves either side of it.
+pub(crate) fn myers(old_length: usize, new_length: usize, equal: impl Fn(usize, usize) -> bool) -> Vec<DiffOp> {
+    let mut ops = Vec::with_capacity(old_length.max(new_length));
+    diff_between(&equal, 0..old_length, 0..new_length, &mut ops);
+    ops
+}
+
+fn diff_between(equal: &impl Fn(usize, usize) -> bool, mut old: Range<usize>, mut new: Range<usize>, ops: &mut Vec<DiffOp>) {
+    while !old.is_empty() && !new.is_empty() && equal(old.start, new.start) {
+        ops.push(DiffOp::Equal);
+        old.start += 1;
+        new.start += 1;
+    }
+    let mut suffix = 0;
+    while !old.is_empty() && !new.is_empty() && equal(old.end - 1, new.end - 1) {
+        suffix += 1;
+        old.end -= 1;
+        new.end -= 1;
+    }
+
+    let split = if old.is_empty() || new.is_empty() {
+        None
+    } else {
+        middle(equal, old.clone(), new.clone())
+    };
+    match split {
+        Some((x, y)) => {
+            diff_between(equal, old.start..x, new.start..y, ops);
+            diff_between(equal, x..old.end, y..new.end, ops);
+        }
+        None => {
+            ops.extend(std::iter::repeat_n(DiffOp::Delete, old.len()));
+            ops.extend(std::iter::repeat_n(DiffOp::Insert, new.len()));
+        }
+    }
+    ops.extend(std::iter::repeat_n(DiffOp::Equal, suffix));
+}
+
+// Runs the forward and backward searches in lockstep until their paths overlap, and returns where
+// the forward one got to, which lies on a shortest path. `None` if the ranges share nothing.
+fn middle(equal: &impl Fn(usize, usize) -> bool, old: Range<usize>, new: Range<usize>) -> Option<(usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let width = 2 * max_d as usize + 2;
+    let mut forward = vec![-1isize; width];
+    let mut backward = vec![-1isize; width];
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    // Diagonals that ran off the grid on either side are skipped in later rounds.
+    let (mut forward_start, mut forward_end, mut backward_start, mut backward_end) = (0, 0, 0, 0);
+    let at = |k: isize| (offset + k) as usize;
+
+    for d in 0..max_d {
+        let mut k = -d + forward_start;
+        while k <= d - forward_end {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && equal(old.start + x as usize, new.start + y as usize) {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            if x > n {
+                forward_end += 2;
+            } else if y > m {
+                forward_start += 2;
+            } else if odd {
+                let mirror = offset + delta - k;
+                if (0..width as isize).contains(&mirror) && backward[mirror as usize] != -1 && x >= n - backward[mirror as usize] {
+                    return Some((old.start + x as usize, new.start + y as usize));
+                }
+            }
+            k += 2;
+        }
+
+        let mut k = -d + backward_start;
+        while k <= d - backward_end {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && equal(old.end - 1 - x as usize, new.end - 1 - y as usize) {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            if x > n {
+                backward_end += 2;
+            } else if y > m {
+                backward_start += 2;
+            } else if !odd {
+                let mirror = offset + delta - k;
+                if (0..width as isize).contains(&mirror) && forward[mirror as usize] != -1 {
+                    let forward_x = forward[mirror as usize];
+                    if forward_x >= n - x {
+                        let forward_y = forward_x - (mirror - offset);
+                        return Some((old.start + forward_x as usize, new.start + forward_y as usize));
+                    }
+                }
+            }
+            k += 2;
+        }
+    }
+    None
+}
+
+pub(crate) fn edits_from_ops<'a>(ops: &[DiffOp], old: &[&'a str], new: &[&'a str]) -> Vec<Edit> {
+    let mut edits: Vec<Edit> = Vec::new();
+    let (mut old_index, mut new_index, mut position) = (0, 0, 0);
+
+    for op in ops {
+        match op {
+            DiffOp::Equal => {
+                position += old[old_index].len();
+                old_index += 1;
+                new_index += 1;
+            }
+            DiffOp::Delete => {
+                let length = old[old_index].len();
+                match edits.last_mut() {
+                    Some(Edit::Delete { range }) if range.start == position => range.end += length,
+                    _ => edits.push(Edit::Delete {
+                        range: position..position + length,
+                    }),
+                }
+                old_index += 1;
+            }
+            DiffOp::Insert => {
+                let text = new[new_index];
+                match edits.last_mut() {
+                    Some(Edit::Insert { offset, text: inserted }) if *offset + inserted.len() == position => inserted.push_str(text),
+                    _ => edits.push(Edit::Insert {
+                        offset: position,
+                        text: text.to_string(),
+                    }),
+                }
+                position += text.len();
+                new_index += 1;
+            }
+        }
+    }
+    edits
+}
+
+impl Snapshot {
+    pub fn diff(&self, other: &Snapshot) -> Vec<Edit> {
+        // Piece bounds only carry over between the snapshots where their buffers hold the same
+        // bytes; anywhere else a bound from one can fall inside a char of the other.
+        let original_prefix = if Arc::ptr_eq(&self.original, &other.original) {
+            self.original.len()
+        } else {
+            0
+        };
+        let add_prefix = self.shared_add_prefix(other);
+        let prefix = |buffer: Buffer| match buffer {
+            Buffer::Original => original_prefix,
+            Buffer::Add => add_prefix,
+        };
+
+        let mut bounds: BTreeMap<Buffer, BTreeSet<usize>> = BTreeMap::new();
+        for piece in self.pieces.iter().chain(other.pieces.iter()) {
+            let buffer_bounds = bounds.entry(piece.buffer).or_default();
+            for bound in [piece.offset, piece.offset + piece.length] {
+                if bound <= prefix(piece.buffer) {
+                    buffer_bounds.insert(bound);
+                }
+            }
+        }
+
+        let old = self.atoms(&bounds, prefix);
+        let new = other.atoms(&bounds, prefix);
+        let ops = myers(old.len(), new.len(), |x, y| same_text(old[x], new[y]));
+        edits_from_ops(&ops, &old, &new)
+    }
+
+    // The part of a piece within the shared prefix of its buffer is split at the piece bounds of
+    // both snapshots, and the rest into chars, which can only match by content.
+    fn atoms(&self, bounds: &BTreeMap<Buffer, BTreeSet<usize>>, prefix: impl Fn(Buffer) -> usize) -> Vec<&str> {
+        let mut atoms = Vec::new();
+        for piece in self.pieces.iter() {
+            let mut start = piece.offset;
+            let end = piece.offset + piece.length;
+            let shared_end = end.min(prefix(piece.buffer)).max(start);
+            let buffer: &str = match piece.buffer {
+                Buffer::Original => &self.original,
+                Buffer::Add => &self.add,
+            };
+            if shared_end > start {
+                for &bound in bounds[&piece.buffer].range(start + 1..shared_end).chain(std::iter::once(&shared_end)) {
+                    atoms.push(&buffer[start..bound]);
+                    start = bound;
+                }
+            }
+            atoms.extend(char_atoms(&buffer[shared_end..end]));
+        }
+        atoms
+    }
+}
+
+impl PieceTable {
+    pub fn diff_with(&self, target: &str) -> Vec<Edit> {
+        let text = self.text();
+
+        let mut prefix = text.bytes().zip(target.bytes()).take_while(|(a, b)| a == b).count();
+        while !text.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let (old, new) = (&text[prefix..], &target[prefix..]);
+
+        let mut suffix = old.bytes().rev().zip(new.bytes().rev()).take_while(|(a, b)| a == b).count();
+        while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+            suffix -= 1;
+        }
+        let old = char_atoms(&old[..old.len() - suffix]);
+        let new = char_atoms(&new[..new.len() - suffix]);
+
+        let ops = myers(old.len(), new.len(), |x, y| old[x] == new[y]);
+        let mut edits = edits_from_ops(&ops, &old, &new);
+        for edit in edits.iter_mut() {
+            match edit {
+                Edit::Insert { offset, .. } => *offset += prefix,
+                Edit::Delete { range } | Edit::Replace { range, .. } => *range = range.start + prefix..range.end + prefix,
+            }
+        }
+        edits
+    }
+}
+
+impl PieceTable {
+    pub fn to_unified_diff(&self, context_lines: usize) -> String {
+        let old: &str = &self.original;
+        let new = self.text();
+        let old_lines = line_ranges(old);
+        let new_lines = line_ranges(&new);
+
+        let mut segments: Vec<(usize, usize, usize)> = Vec::new();
+        let (mut position, mut original_end) = (0, 0);
+        for piece in &self.pieces {
+            if piece.buffer == Buffer::Original && piece.offset >= original_end {
+                match segments.last_mut() {
+                    Some((old_start, new_start, length)) if *old_start + *length == piece.offset && *new_start + *length == position => {
+                        *length += piece.length
+                    }
+                    _ => segments.push((piece.offset, position, piece.length)),
+                }
+                original_end = piece.offset + piece.length;
+            }
+            position += piece.length;
+        }
+
+        let mut matches = Vec::new();
+        let mut segment = 0;
+        for (old_index, line) in old_lines.iter().enumerate() {
+            while segment < segments.len() && segments[segment].0 + segments[segment].2 < line.end {
+                segment += 1;
+            }
+            let Some(&(old_start, new_start, _)) = segments.get(segment) else {
+                break;
+            };
+            if line.start < old_start {
+                continue;
+            }
+
+            let start = new_start + line.start - old_start;
+            if let Ok(new_index) = new_lines.binary_search_by_key(&start, |range| range.start) {
+                if new_lines[new_index].end == start + line.len() {
+                    matches.push((old_index, new_index));
+                }
+            }
+        }
+
+        let mut entries: Vec<(char, &str)> = Vec::new();
+        let (mut old_index, mut new_index) = (0, 0);
+        for &(old_match, new_match) in matches.iter().chain(std::iter::once(&(old_lines.len(), new_lines.len()))) {
+            entries.extend(old_lines[old_index..old_match].iter().map(|line| ('-', &old[line.clone()])));
+            entries.extend(new_lines[new_index..new_match].iter().map(|line| ('+', &new[line.clone()])));
+            if old_match < old_lines.len() {
+                entries.push((' ', &old[old_lines[old_match].clone()]));
+            }
+            old_index = old_match + 1;
+            new_index = new_match + 1;
+        }
+
+        let changes: Vec<usize> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (tag, _))| *tag != ' ')
+            .map(|(index, _)| index)
+            .collect();
+        if changes.is_empty() {
+            return String::new();
+        }
+
+        let mut hunks: Vec<Range<usize>> = Vec::new();
+        for &change in &changes {
+            let start = change.saturating_sub(context_lines);
+            let end = (change + context_lines + 1).min(entries.len());
+            match hunks.last_mut() {
+                Some(hunk) if start <= hunk.end => hunk.end = end,
+                _ => hunks.push(start..end),
+            }
+        }
+
+        let mut diff = String::from("--- original\n+++ modified\n");
+        for hunk in hunks {
+            let old_start = entries[..hunk.start].iter().filter(|(tag, _)| *tag != '+').count();
+            let new_start = entries[..hunk.start].iter().filter(|(tag, _)| *tag != '-').count();
+            let old_count = entries[hunk.clone()].iter().filter(|(tag, _)| *tag != '+').count();
+            let new_count = entries[hunk.clone()].iter().filter(|(tag, _)| *tag != '-').count();
+
+            let _ = writeln!(diff, "@@ -{} +{} @@", hunk_range(old_start, old_count), hunk_range(new_start, new_count));
+            for (tag, line) in &entries[hunk] {
+                diff.push(*tag);
+                diff.push_str(line);
+                if !line.ends_with('\n') {
+                    diff.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+        diff
+    }
+}
+
+fn line_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut start = 0;
+    text.split_inclusive('\n')
+        .map(|line| {
+            start += line.len();
+            start - line.len()..start
+        })
+        .collect()
+}
+
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{count}", start + 1),
+    }
+}
+
+fn char_atoms(text: &str) -> Vec<&str> {
+    text.char_indices().map(|(index, ch)| &text[index..index + ch.len_utf8()]).collect()
+}
+
+fn same_text(a: &str, b: &str) -> bool {
+    std::ptr::eq(a, b) || a == b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PieceTable;
+
+    #[test]
+    fn test_myers() {
+        let old: Vec<char> = "ABCABBA".chars().collect();
+        let new: Vec<char> = "CBABAC".chars().collect();
+        let ops = myers(old.len(), new.len(), |x, y| old[x] == new[y]);
+
+        assert_eq!(4, ops.iter().filter(|op| **op == DiffOp::Equal).count());
+        assert_eq!(5, ops.len() - 4);
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let mut piece_table = PieceTable::new("the quick brown fox".to_string());
+        let before = piece_table.snapshot();
+
+        piece_table.delete(4, 6);
+        piece_table.insert(4, "slow ");
+        piece_table.insert(18, " jumps");
+        let after = piece_table.snapshot();
+
+        let edits = before.diff(&after);
+        assert_eq!(
+            vec![
+                Edit::Delete { range: 4..10 },
+                Edit::Insert {
+                    offset: 4,
+                    text: "slow ".to_string()
+                },
+                Edit::Insert {
+                    offset: 18,
+                    text: " jumps".to_string()
+                },
+            ],
+            edits
+        );
+        assert_eq!(after.text(), PieceTable::replay(before.text(), &edits).text());
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_diff_diverged() {
+        let base = PieceTable::from("x");
+        let mut left = base.clone();
+        left.push_str("é");
+        let mut right = base.clone();
+        right.push_str("aé");
+
+        let edits = left.snapshot().diff(&right.snapshot());
+        assert_eq!(
+            vec![Edit::Insert {
+                offset: 1,
+                text: "a".to_string()
+            }],
+            edits
+        );
+        assert_eq!("xaé", PieceTable::replay(left.text(), &edits).text());
+
+        let (left, right) = (PieceTable::from("é"), PieceTable::from("aé"));
+        let edits = left.snapshot().diff(&right.snapshot());
+        assert_eq!("aé", PieceTable::replay(left.text(), &edits).text());
+    }
+
+    #[test]
+    fn test_diff_with() {
+        let piece_table = PieceTable::new("fn main() { println!(\"hi\"); }".to_string());
+        let target = "fn main() {\n    println!(\"héllo\");\n}";
+
+        let edits = piece_table.diff_with(target);
+        assert_eq!(target, PieceTable::replay(piece_table.text(), &edits).text());
+        assert!(matches!(edits[0], Edit::Insert { offset: 11, .. }));
+        assert!(piece_table.diff_with(&piece_table.text()).is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let mut piece_table = PieceTable::new("one\ntwo\nthree\nfour\nfive\nsix\n".to_string());
+        assert_eq!("", piece_table.to_unified_diff(3));
+
+        piece_table.delete(4, 4);
+        piece_table.insert(4, "TWO\n");
+        piece_table.insert(piece_table.length(), "seven");
+
+        let expected = "--- original
++++ modified
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+@@ -6 +6,2 @@
+ six
++seven
+\\ No newline at end of file
+";
+        assert_eq!(expected, piece_table.to_unified_diff(1));
+    }
+}
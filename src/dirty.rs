@@ -0,0 +1,165 @@
+use crate::{Edit, PieceTable};
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+const MAX_TRACKED_CHANGES: usize = 4096;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Entry {
+    revision: u64,
+    offset: usize,
+    removed: usize,
+    inserted: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct StaleRevision {
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl Display for StaleRevision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "edits were made against revision {} but the table is at revision {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for StaleRevision {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct ChangeLog {
+    revision: u64,
+    entries: VecDeque<Entry>,
+}
+
+impl ChangeLog {
+    pub(crate) fn record(&mut self, offset: usize, removed: usize, inserted: usize) {
+        self.revision += 1;
+        if self.entries.len() == MAX_TRACKED_CHANGES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry {
+            revision: self.revision,
+            offset,
+            removed,
+            inserted,
+        });
+    }
+
+    fn since(&self, revision: u64, length: usize) -> Vec<Range<usize>> {
+        if revision >= self.revision {
+            return Vec::new();
+        }
+        if self.entries.front().is_none_or(|entry| entry.revision > revision + 1) {
+            return std::iter::once(0..length).collect();
+        }
+
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for entry in self.entries.iter().filter(|entry| entry.revision > revision) {
+            let removed_end = entry.offset + entry.removed;
+            for range in ranges.iter_mut() {
+                if range.end < entry.offset {
+                    continue;
+                }
+                if range.start > removed_end {
+                    range.start = range.start - entry.removed + entry.inserted;
+                    range.end = range.end - entry.removed + entry.inserted;
+                } else {
+                    range.start = range.start.min(entry.offset);
+                    range.end = range.end.max(removed_end) - entry.removed + entry.inserted;
+                }
+            }
+            ranges.push(entry.offset..entry.offset + entry.inserted);
+        }
+
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+impl PieceTable {
+    pub fn revision(&self) -> u64 {
+        self.changes.revision
+    }
+
+    pub fn changes_since(&self, revision: u64) -> Vec<Range<usize>> {
+        self.changes.since(revision, self.length())
+    }
+
+    pub fn apply_if(&mut self, revision: u64, edits: &[Edit]) -> Result<u64, StaleRevision> {
+        if revision != self.revision() {
+            return Err(StaleRevision {
+                expected: revision,
+                actual: self.revision(),
+            });
+        }
+        self.transaction(|table| {
+            for edit in edits {
+                table.apply_edit(edit);
+            }
+        });
+        Ok(self.revision())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_since() {
+        let mut piece_table = PieceTable::new("0123456789".to_string());
+        let start = piece_table.revision();
+
+        piece_table.insert(2, "ab");
+        piece_table.delete(8, 2);
+        let middle = piece_table.revision();
+        assert_eq!(start + 2, middle);
+        assert_eq!(vec![2..4, 8..8], piece_table.changes_since(start));
+
+        piece_table.insert(0, "xyz");
+        assert_eq!(vec![0..3, 5..7, 11..11], piece_table.changes_since(start));
+        assert_eq!(Some(0..3), piece_table.changes_since(middle).pop());
+
+        piece_table.undo();
+        assert_eq!(Some(0..0), piece_table.changes_since(middle).pop());
+        assert!(piece_table.changes_since(piece_table.revision()).is_empty());
+    }
+
+    #[test]
+    fn test_apply_if() {
+        let mut piece_table = PieceTable::new("hello".to_string());
+        let token = piece_table.revision();
+        let edits = [Edit::Insert {
+            offset: 5,
+            text: " world".to_string(),
+        }];
+
+        let next = piece_table.apply_if(token, &edits).unwrap();
+        assert_eq!("hello world", piece_table.text());
+        assert!(next > token);
+
+        assert_eq!(
+            Err(StaleRevision {
+                expected: token,
+                actual: next,
+            }),
+            piece_table.apply_if(token, &edits)
+        );
+        assert_eq!("hello world", piece_table.text());
+    }
+}
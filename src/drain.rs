@@ -0,0 +1,67 @@
+use crate::{Piece, PieceTable};
+use std::ops::{Range, RangeBounds};
+use std::str::Chars;
+
+#[derive(Debug, Clone)]
+pub struct Drain<'a> {
+    table: &'a PieceTable,
+    pieces: std::vec::IntoIter<Piece>,
+    chars: Chars<'a>,
+}
+
+impl Iterator for Drain<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(ch) = self.chars.next() {
+                return Some(ch);
+            }
+            let piece = self.pieces.next()?;
+            self.chars = self.table.piece_text(&piece).chars();
+        }
+    }
+}
+
+impl PieceTable {
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_> {
+        let Range { start, end } = self.resolve_range(range);
+
+        let removed = if start < end {
+            let change = self.replace_pieces(start, end - start, Vec::new());
+            let removed = change.removed.clone();
+            self.history.record(change);
+            removed
+        } else {
+            Vec::new()
+        };
+
+        Drain {
+            table: self,
+            pieces: removed.into_iter(),
+            chars: "".chars(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain() {
+        let mut piece_table = PieceTable::new("héllo wörld".to_string());
+        piece_table.insert(7, "big ");
+
+        let drained: String = piece_table.drain(3..11).collect();
+        assert_eq!("llo big ", drained);
+        assert_eq!("héwörld", piece_table.text());
+
+        assert_eq!(Some('w'), piece_table.drain(3..100).next());
+        assert_eq!("hé", piece_table.text());
+        assert_eq!(0, piece_table.drain(2..2).count());
+
+        assert!(piece_table.undo());
+        assert_eq!("héwörld", piece_table.text());
+    }
+}
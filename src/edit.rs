@@ -0,0 +1,294 @@
+use crate::PieceTable;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Edit {
+    Insert { offset: usize, text: String },
+    Delete { range: Range<usize> },
+    Replace { range: Range<usize>, text: String },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Overlap {
+    Disjoint,
+    Adjacent,
+    Overlapping,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct OverlappingEdits {
+    pub first: usize,
+    pub second: usize,
+}
+
+impl Display for OverlappingEdits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "edits {} and {} overlap", self.first, self.second)
+    }
+}
+
+impl std::error::Error for OverlappingEdits {}
+
+impl Edit {
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            Edit::Insert { offset, .. } => *offset..*offset,
+            Edit::Delete { range } | Edit::Replace { range, .. } => range.clone(),
+        }
+    }
+
+    pub fn primitives(&self) -> Vec<Edit> {
+        match self {
+            Edit::Replace { range, text } => vec![
+                Edit::Delete { range: range.clone() },
+                Edit::Insert {
+                    offset: range.start,
+                    text: text.clone(),
+                },
+            ],
+            edit => vec![edit.clone()],
+        }
+    }
+
+    pub fn overlap(&self, other: &Edit) -> Overlap {
+        let (a, b) = (self.range(), other.range());
+        let same_insertion_point = a.is_empty() && b.is_empty() && a.start == b.start;
+        if same_insertion_point || (a.start < b.end && b.start < a.end) {
+            Overlap::Overlapping
+        } else if a.end == b.start || b.end == a.start {
+            Overlap::Adjacent
+        } else {
+            Overlap::Disjoint
+        }
+    }
+}
+
+impl PieceTable {
+    pub fn replay(original: String, log: &[Edit]) -> PieceTable {
+        let mut piece_table = PieceTable::new(original);
+        for edit in log {
+            piece_table.apply_edit(edit);
+        }
+        piece_table
+    }
+
+    pub fn apply(&mut self, edit: Edit) {
+        self.apply_edit(&edit);
+    }
+
+    /// Returns the edit that undoes `edit` once it has been applied to this table. Offsets are
+    /// rounded to char boundaries and inserted text sized after line ending normalization, as
+    /// applying the edit does.
+    pub fn invert(&self, edit: &Edit) -> Edit {
+        match edit {
+            Edit::Insert { offset, text } => {
+                let offset = self.floor_char_boundary(*offset);
+                Edit::Delete {
+                    range: offset..offset + self.normalize_insert(text).len(),
+                }
+            }
+            Edit::Delete { range } => {
+                let range = self.resolve_range(range.clone());
+                Edit::Insert {
+                    offset: range.start,
+                    text: self.slice(range),
+                }
+            }
+            Edit::Replace { range, text } => {
+                let range = self.resolve_range(range.clone());
+                Edit::Replace {
+                    range: range.start..range.start + self.normalize_insert(text).len(),
+                    text: self.slice(range),
+                }
+            }
+        }
+    }
+
+    pub fn apply_edits(&mut self, edits: &[Edit]) -> Result<(), OverlappingEdits> {
+        let mut order: Vec<usize> = (0..edits.len()).collect();
+        order.sort_by_key(|&index| (edits[index].range().start, edits[index].range().end));
+        for pair in order.windows(2) {
+            if edits[pair[0]].overlap(&edits[pair[1]]) == Overlap::Overlapping {
+                return Err(OverlappingEdits {
+                    first: pair[0].min(pair[1]),
+                    second: pair[0].max(pair[1]),
+                });
+            }
+        }
+
+        self.transaction(|table| {
+            for &index in order.iter().rev() {
+                table.apply_edit(&edits[index]);
+            }
+        });
+        Ok(())
+    }
+
+    pub(crate) fn apply_edit(&mut self, edit: &Edit) {
+        match edit {
+            Edit::Insert { offset, text } => self.insert(*offset, text),
+            Edit::Delete { range } => self.delete(range.start, range.len()),
+            Edit::Replace { range, text } => self.replace(range.clone(), text),
+        }
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording.get_or_insert_with(Vec::new);
+    }
+
+    pub fn stop_recording(&mut self) -> Vec<Edit> {
+        self.recording.take().unwrap_or_default()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn recorded_edits(&self) -> &[Edit] {
+        self.recording.as_deref().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LineEnding;
+
+    #[test]
+    fn test_replay() {
+        let mut piece_table = PieceTable::new("hello world".to_string());
+        piece_table.insert(0, "ignored ");
+        piece_table.start_recording();
+
+        piece_table.delete(0, 8);
+        piece_table.insert(5, ",");
+        piece_table.delete(7, 5);
+        piece_table.insert(7, "there");
+        piece_table.undo();
+        piece_table.redo();
+        piece_table.undo();
+
+        let log = piece_table.stop_recording();
+        assert_eq!(
+            Edit::Insert {
+                offset: 5,
+                text: ",".to_string()
+            },
+            log[1]
+        );
+        assert_eq!(Edit::Delete { range: 7..12 }, log[4]);
+        assert!(!piece_table.is_recording());
+
+        let replayed = PieceTable::replay("ignored hello world".to_string(), &log);
+        assert_eq!("hello, ", replayed.text());
+        assert_eq!(piece_table.text(), replayed.text());
+    }
+
+    #[test]
+    fn test_apply_and_invert() {
+        let mut piece_table = PieceTable::new("hello world".to_string());
+        let edits = [
+            Edit::Replace {
+                range: 0..5,
+                text: "howdy".to_string(),
+            },
+            Edit::Insert {
+                offset: 11,
+                text: "!".to_string(),
+            },
+            Edit::Delete { range: 5..6 },
+        ];
+
+        let mut inverses = Vec::new();
+        for edit in edits {
+            inverses.push(piece_table.invert(&edit));
+            piece_table.apply(edit);
+        }
+        assert_eq!("howdyworld!", piece_table.text());
+        assert_eq!(
+            Edit::Replace {
+                range: 0..5,
+                text: "hello".to_string(),
+            },
+            inverses[0]
+        );
+
+        for inverse in inverses.into_iter().rev() {
+            piece_table.apply(inverse);
+        }
+        assert_eq!("hello world", piece_table.text());
+    }
+
+    #[test]
+    fn test_invert_rounds_to_char_boundaries() {
+        let edits = [
+            Edit::Insert {
+                offset: 1,
+                text: "ab".to_string(),
+            },
+            Edit::Delete { range: 1..2 },
+            Edit::Replace {
+                range: 1..2,
+                text: "xyz".to_string(),
+            },
+        ];
+        for edit in edits {
+            let mut piece_table = PieceTable::from("é");
+            let inverse = piece_table.invert(&edit);
+            piece_table.apply(edit);
+            piece_table.apply(inverse);
+            assert_eq!("é", piece_table.text());
+        }
+    }
+
+    #[test]
+    fn test_invert_normalized_insert() {
+        let edits = [
+            Edit::Insert {
+                offset: 1,
+                text: "a\nb".to_string(),
+            },
+            Edit::Replace {
+                range: 0..1,
+                text: "\n\n".to_string(),
+            },
+        ];
+        for edit in edits {
+            let mut piece_table = PieceTable::from("xy");
+            piece_table.set_insert_line_ending(Some(LineEnding::Crlf));
+            let inverse = piece_table.invert(&edit);
+            piece_table.apply(edit);
+            piece_table.apply(inverse);
+            assert_eq!("xy", piece_table.text());
+        }
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let mut piece_table = PieceTable::new("let a = b + c;".to_string());
+        let cursors = [
+            Edit::Replace {
+                range: 12..13,
+                text: "gamma".to_string(),
+            },
+            Edit::Insert {
+                offset: 0,
+                text: "// ".to_string(),
+            },
+            Edit::Replace {
+                range: 4..5,
+                text: "alpha".to_string(),
+            },
+            Edit::Delete { range: 9..13 },
+        ];
+        assert_eq!(Err(OverlappingEdits { first: 0, second: 3 }), piece_table.apply_edits(&cursors));
+        assert_eq!("let a = b + c;", piece_table.text());
+
+        piece_table.apply_edits(&cursors[..3]).unwrap();
+        assert_eq!("// let alpha = b + gamma;", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("let a = b + c;", piece_table.text());
+    }
+}
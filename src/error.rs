@@ -0,0 +1,79 @@
+use crate::PieceTable;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PieceTableError {
+    OffsetOutOfBounds { offset: usize, length: usize },
+    NotCharBoundary { offset: usize },
+    LengthOverflow { offset: usize, length: usize },
+}
+
+impl Display for PieceTableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PieceTableError::OffsetOutOfBounds { offset, length } => write!(f, "offset {offset} is out of bounds for length {length}"),
+            PieceTableError::NotCharBoundary { offset } => write!(f, "offset {offset} is not a char boundary"),
+            PieceTableError::LengthOverflow { offset, length } => write!(f, "deleting {length} bytes at offset {offset} overflows"),
+        }
+    }
+}
+
+impl std::error::Error for PieceTableError {}
+
+impl PieceTable {
+    pub fn try_insert(&mut self, offset: usize, content: &str) -> Result<(), PieceTableError> {
+        self.check_offset(offset)?;
+        self.insert(offset, content);
+        Ok(())
+    }
+
+    pub fn try_delete(&mut self, offset: usize, length: usize) -> Result<(), PieceTableError> {
+        let end = offset.checked_add(length).ok_or(PieceTableError::LengthOverflow { offset, length })?;
+        self.check_offset(offset)?;
+        self.check_offset(end)?;
+        self.delete(offset, length);
+        Ok(())
+    }
+
+    fn check_offset(&self, offset: usize) -> Result<(), PieceTableError> {
+        let length = self.length();
+        if offset > length {
+            Err(PieceTableError::OffsetOutOfBounds { offset, length })
+        } else if !self.is_char_boundary(offset) {
+            Err(PieceTableError::NotCharBoundary { offset })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_insert_and_delete() {
+        let mut piece_table = PieceTable::from("añb");
+        assert_eq!(Err(PieceTableError::NotCharBoundary { offset: 2 }), piece_table.try_insert(2, "x"));
+        assert_eq!(
+            Err(PieceTableError::OffsetOutOfBounds { offset: 5, length: 4 }),
+            piece_table.try_insert(5, "x")
+        );
+        assert_eq!(
+            Err(PieceTableError::LengthOverflow {
+                offset: 1,
+                length: usize::MAX,
+            }),
+            piece_table.try_delete(1, usize::MAX)
+        );
+        assert_eq!(
+            Err(PieceTableError::OffsetOutOfBounds { offset: 6, length: 4 }),
+            piece_table.try_delete(1, 5)
+        );
+        assert_eq!("añb", piece_table.text());
+
+        piece_table.try_insert(3, "x").unwrap();
+        piece_table.try_delete(1, 2).unwrap();
+        assert_eq!("axb", piece_table.text());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::PieceTable;
+use std::ops::{Range, RangeBounds};
+
+const SCORE_MATCH: i32 = 16;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTENSION: i32 = 1;
+const BONUS_BOUNDARY_WHITE: i32 = 10;
+const BONUS_BOUNDARY_DELIMITER: i32 = 9;
+const BONUS_NON_WORD: i32 = 8;
+const BONUS_CAMEL: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+const BONUS_FIRST_MULTIPLIER: i32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuzzyMatch {
+    pub line: usize,
+    pub score: i32,
+    /// From the first matched char to the end of the last one.
+    pub range: Range<usize>,
+    /// The offset of each matched char, one per char of the query.
+    pub positions: Vec<usize>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Class {
+    White,
+    Delimiter,
+    NonWord,
+    Lower,
+    Upper,
+    Digit,
+}
+
+impl Class {
+    fn of(ch: char) -> Self {
+        match ch {
+            _ if ch.is_whitespace() => Class::White,
+            '/' | '\\' | '_' | '-' | '.' | ',' | ':' | ';' | '|' => Class::Delimiter,
+            _ if ch.is_lowercase() => Class::Lower,
+            _ if ch.is_uppercase() => Class::Upper,
+            _ if ch.is_numeric() => Class::Digit,
+            _ if ch.is_alphabetic() => Class::Lower,
+            _ => Class::NonWord,
+        }
+    }
+
+    fn is_word(self) -> bool {
+        matches!(self, Class::Lower | Class::Upper | Class::Digit)
+    }
+}
+
+fn bonus(previous: Class, class: Class) -> i32 {
+    match (previous, class) {
+        (_, class) if !class.is_word() => BONUS_NON_WORD,
+        (Class::White, _) => BONUS_BOUNDARY_WHITE,
+        (Class::Delimiter, _) => BONUS_BOUNDARY_DELIMITER,
+        (Class::NonWord, _) => BONUS_NON_WORD,
+        (Class::Lower, Class::Upper) | (Class::Lower | Class::Upper, Class::Digit) => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+impl PieceTable {
+    /// Matches `query` as a subsequence of every line, fzf style, and returns the lines that match,
+    /// best score first. Case is ignored unless the query contains an uppercase letter.
+    pub fn fuzzy_find(&self, query: &str) -> Vec<FuzzyMatch> {
+        let mut matches: Vec<FuzzyMatch> = (0..self.line_count())
+            .filter_map(|line| self.fuzzy_match(query, self.line_range(line)))
+            .collect();
+        matches.sort_by_key(|found| -found.score);
+        matches
+    }
+
+    /// Scores `query` against the text in `range`, such as `..` for the whole document.
+    pub fn fuzzy_match(&self, query: &str, range: impl RangeBounds<usize>) -> Option<FuzzyMatch> {
+        let range = self.resolve_range(range);
+        let query: Vec<char> = query.chars().collect();
+        if query.is_empty() {
+            return None;
+        }
+        let case_sensitive = query.iter().any(|ch| ch.is_uppercase());
+        let matches = |ch: char, query: char| ch == query || !case_sensitive && ch.to_lowercase().eq(query.to_lowercase());
+
+        let text = self.slice_cow(range.clone());
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+
+        // Find where the earliest complete match ends, then walk back from there to the latest
+        // start, which gives the shortest window containing that match.
+        let mut next = 0;
+        let mut end = None;
+        for (index, &(_, ch)) in chars.iter().enumerate() {
+            if matches(ch, query[next]) {
+                next += 1;
+                if next == query.len() {
+                    end = Some(index);
+                    break;
+                }
+            }
+        }
+        let end = end?;
+        let mut start = end;
+        let mut remaining = query.len();
+        for index in (0..=end).rev() {
+            if matches(chars[index].1, query[remaining - 1]) {
+                remaining -= 1;
+                if remaining == 0 {
+                    start = index;
+                    break;
+                }
+            }
+        }
+
+        let mut score = 0;
+        let mut positions = Vec::with_capacity(query.len());
+        let mut previous = start.checked_sub(1).map_or(Class::White, |index| Class::of(chars[index].1));
+        let mut in_gap = false;
+        let mut consecutive = 0;
+        let mut run_bonus = 0;
+        for &(offset, ch) in &chars[start..=end] {
+            let class = Class::of(ch);
+            if positions.len() < query.len() && matches(ch, query[positions.len()]) {
+                let mut bonus = bonus(previous, class);
+                if consecutive == 0 {
+                    run_bonus = bonus;
+                } else {
+                    bonus = bonus.max(run_bonus).max(BONUS_CONSECUTIVE);
+                }
+                score += SCORE_MATCH + if positions.is_empty() { bonus * BONUS_FIRST_MULTIPLIER } else { bonus };
+                positions.push(range.start + offset);
+                in_gap = false;
+                consecutive += 1;
+            } else {
+                score -= if in_gap { PENALTY_GAP_EXTENSION } else { PENALTY_GAP_START };
+                in_gap = true;
+                consecutive = 0;
+                run_bonus = 0;
+            }
+            previous = class;
+        }
+
+        let (last, ch) = chars[end];
+        Some(FuzzyMatch {
+            line: self.byte_to_line(range.start + chars[start].0),
+            score,
+            range: range.start + chars[start].0..range.start + last + ch.len_utf8(),
+            positions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_find() {
+        let mut piece_table = PieceTable::from("src/main.rs\nsrc/piece_table.rs\nREADME.md\ntests/mod.rs");
+        piece_table.insert(12, "ffi/");
+
+        let found = piece_table.fuzzy_find("pt");
+        assert_eq!(1, found.len());
+        assert_eq!(1, found[0].line);
+        assert_eq!(vec![20, 26], found[0].positions);
+        assert_eq!("piece_t", piece_table.slice(found[0].range.clone()));
+
+        let lines: Vec<usize> = piece_table.fuzzy_find("mrs").into_iter().map(|found| found.line).collect();
+        assert_eq!(vec![3, 0], lines);
+        assert!(piece_table.fuzzy_find("Readme").is_empty());
+        assert_eq!(2, piece_table.fuzzy_find("readme")[0].line);
+        assert!(piece_table.fuzzy_match("main\nsrc", ..).is_some());
+        assert!(piece_table.fuzzy_find("").is_empty());
+    }
+}
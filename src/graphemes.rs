@@ -0,0 +1,109 @@
+use crate::PieceTable;
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone)]
+pub struct Graphemes<'a> {
+    table: &'a PieceTable,
+    offset: usize,
+}
+
+impl Graphemes<'_> {
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (chunk, chunk_start) = self.table.chunk_at(self.offset);
+        let rest = &chunk[self.offset - chunk_start..];
+        let first = rest.graphemes(true).next()?;
+        let mut end = chunk_start + chunk.len();
+        if first.len() < rest.len() || end == self.table.length() {
+            self.offset += first.len();
+            return Some(Cow::Borrowed(first));
+        }
+
+        // The cluster reaches the end of this piece, so it may continue into the next ones.
+        let mut window = rest.to_string();
+        loop {
+            let (next, _) = self.table.chunk_at(end);
+            window.push_str(next);
+            end += next.len();
+
+            let length = window.graphemes(true).next().map_or(0, str::len);
+            if length < window.len() || end == self.table.length() {
+                self.offset += length;
+                if length == first.len() {
+                    return Some(Cow::Borrowed(first));
+                }
+                window.truncate(length);
+                return Some(Cow::Owned(window));
+            }
+        }
+    }
+}
+
+impl PieceTable {
+    pub fn graphemes(&self) -> Graphemes<'_> {
+        self.graphemes_at(0)
+    }
+
+    pub fn graphemes_at(&self, offset: usize) -> Graphemes<'_> {
+        Graphemes {
+            table: self,
+            offset: self.floor_char_boundary(offset),
+        }
+    }
+
+    pub fn grapheme_at(&self, index: usize) -> Option<Cow<'_, str>> {
+        self.graphemes().nth(index)
+    }
+
+    pub fn len_graphemes(&self) -> usize {
+        self.graphemes().count()
+    }
+
+    pub(crate) fn chunk_at(&self, offset: usize) -> (&str, usize) {
+        match self.piece_at(offset) {
+            Some((piece, _, running_total)) => (self.piece_text(&piece), running_total),
+            None => ("", self.length()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphemes() {
+        let mut piece_table = PieceTable::from("ae\u{301}🇩");
+        piece_table.push_str("🇪👍🏽!");
+        assert_eq!(vec!["a", "e\u{301}", "🇩🇪", "👍🏽", "!"], piece_table.graphemes().collect::<Vec<_>>());
+        assert!(matches!(piece_table.graphemes().nth(1), Some(Cow::Borrowed(_))));
+        assert!(matches!(piece_table.graphemes().nth(2), Some(Cow::Owned(_))));
+
+        let mut graphemes = piece_table.graphemes_at(4);
+        assert_eq!(Some(Cow::Borrowed("🇩🇪")), graphemes.next());
+        assert_eq!(12, graphemes.offset());
+        assert_eq!(vec!["👍🏽", "!"], graphemes.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_grapheme_at() {
+        let mut piece_table = PieceTable::from("ne\u{301}e");
+        piece_table.insert(1, "👩\u{200d}");
+        piece_table.insert(8, "💻");
+        assert_eq!("n👩\u{200d}💻e\u{301}e", piece_table.text());
+
+        assert_eq!(4, piece_table.len_graphemes());
+        assert_eq!(Some("👩\u{200d}💻".into()), piece_table.grapheme_at(1));
+        assert_eq!(Some("e\u{301}".into()), piece_table.grapheme_at(2));
+        assert_eq!(None, piece_table.grapheme_at(4));
+        assert_eq!(0, PieceTable::default().len_graphemes());
+    }
+}
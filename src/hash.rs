@@ -0,0 +1,71 @@
+use crate::PieceTable;
+use std::sync::Mutex;
+
+/// The last computed content hash and the revision it was computed at.
+#[derive(Debug, Default)]
+pub(crate) struct HashCache(Mutex<Option<(u64, u64)>>);
+
+impl Clone for HashCache {
+    fn clone(&self) -> Self {
+        HashCache(Mutex::new(*self.0.lock().expect("content hash lock")))
+    }
+}
+
+impl PieceTable {
+    /// Hashes the text chunk by chunk, so equal texts hash alike however they are split into
+    /// pieces. The result is cached until the next edit. It uses xxh3 with the `xxhash` feature
+    /// and std's `DefaultHasher` otherwise, so only compare hashes from the same build.
+    pub fn content_hash(&self) -> u64 {
+        let mut cache = self.content_hash.0.lock().expect("content hash lock");
+        match *cache {
+            Some((revision, hash)) if revision == self.revision() => hash,
+            _ => {
+                let hash = hash_chunks(self.chunks());
+                *cache = Some((self.revision(), hash));
+                hash
+            }
+        }
+    }
+}
+
+#[cfg(feature = "xxhash")]
+fn hash_chunks<'a>(chunks: impl Iterator<Item = &'a str>) -> u64 {
+    let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+    for chunk in chunks {
+        hasher.update(chunk.as_bytes());
+    }
+    hasher.digest()
+}
+
+#[cfg(not(feature = "xxhash"))]
+fn hash_chunks<'a>(chunks: impl Iterator<Item = &'a str>) -> u64 {
+    use std::hash::{DefaultHasher, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    for chunk in chunks {
+        hasher.write(chunk.as_bytes());
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash() {
+        let mut piece_table = PieceTable::from("hello world");
+        let hash = piece_table.content_hash();
+        assert_eq!(hash, piece_table.content_hash());
+
+        piece_table.insert(5, ",");
+        piece_table.insert(0, "> ");
+        assert_ne!(hash, piece_table.content_hash());
+        assert_eq!(PieceTable::from("> hello, world").content_hash(), piece_table.content_hash());
+
+        piece_table.undo();
+        piece_table.undo();
+        assert_eq!(hash, piece_table.content_hash());
+        assert_eq!(hash, piece_table.clone().content_hash());
+    }
+}
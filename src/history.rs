@@ -0,0 +1,436 @@
+use crate::lines::LineBreaks;
+use crate::{splice_pieces, Piece};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeId(usize);
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HistoryMode {
+    #[default]
+    Linear,
+    Tree,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coalesce {
+    pub window: Option<Duration>,
+    pub word_boundary: bool,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryLimit {
+    pub max_entries: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryNode {
+    pub id: NodeId,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Change {
+    pub(crate) offset: usize,
+    pub(crate) removed: Vec<Piece>,
+    pub(crate) inserted: Vec<Piece>,
+}
+
+impl Change {
+    pub(crate) fn inverse(&self) -> Change {
+        Change {
+            offset: self.offset,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+
+    pub(crate) fn removed_length(&self) -> usize {
+        self.removed.iter().map(|piece| piece.length).sum()
+    }
+
+    fn estimated_bytes(&self) -> usize {
+        size_of::<Change>() + (self.removed.len() + self.inserted.len()) * size_of::<Piece>()
+    }
+
+    pub(crate) fn inserted_length(&self) -> usize {
+        self.inserted.iter().map(|piece| piece.length).sum()
+    }
+
+    fn delta(&self) -> isize {
+        self.inserted_length() as isize - self.removed_length() as isize
+    }
+
+    fn shift(&mut self, delta: isize) {
+        self.offset = self.offset.checked_add_signed(delta).expect("shifted offset stays in bounds");
+    }
+}
+
+pub(crate) fn rebase(changes: &mut [Change], over: &Change) -> bool {
+    let mut over = over.clone();
+    for change in changes.iter_mut() {
+        let change_end = change.offset + change.removed_length();
+        let over_end = over.offset + over.removed_length();
+
+        if over_end <= change.offset {
+            change.shift(over.delta());
+        } else if change_end <= over.offset {
+            over.shift(change.delta());
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Step {
+    pub(crate) changes: Vec<Change>,
+}
+
+impl Step {
+    fn estimated_bytes(&self) -> usize {
+        self.changes.iter().map(Change::estimated_bytes).sum()
+    }
+
+    pub(crate) fn apply_to(&self, pieces: &mut Vec<Piece>, line_breaks: &LineBreaks) {
+        for change in &self.changes {
+            splice_pieces(pieces, line_breaks, change.offset, change.removed_length(), &change.inserted);
+        }
+    }
+
+    pub(crate) fn revert_from(&self, pieces: &mut Vec<Piece>, line_breaks: &LineBreaks) {
+        for change in self.changes.iter().rev() {
+            splice_pieces(pieces, line_breaks, change.offset, change.inserted_length(), &change.removed);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Node {
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    active_child: Option<NodeId>,
+    step: Step,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct History {
+    nodes: BTreeMap<NodeId, Node>,
+    root: NodeId,
+    current: NodeId,
+    next_id: usize,
+    pub(crate) mode: HistoryMode,
+    pub(crate) coalesce: Option<Coalesce>,
+    pub(crate) limit: HistoryLimit,
+    pub(crate) checkpoints: BTreeMap<String, NodeId>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_typed: Option<Instant>,
+    pending: Step,
+    depth: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        let root = Node {
+            parent: None,
+            children: Vec::new(),
+            active_child: None,
+            step: Step::default(),
+        };
+        History {
+            nodes: BTreeMap::from([(NodeId(0), root)]),
+            root: NodeId(0),
+            current: NodeId(0),
+            next_id: 1,
+            mode: HistoryMode::default(),
+            coalesce: None,
+            limit: HistoryLimit::default(),
+            checkpoints: BTreeMap::new(),
+            last_typed: None,
+            pending: Step::default(),
+            depth: 0,
+        }
+    }
+}
+
+impl History {
+    pub(crate) fn record(&mut self, change: Change) {
+        self.last_typed = None;
+        self.pending.changes.push(change);
+        if self.depth == 0 {
+            self.flush();
+        }
+    }
+
+    pub(crate) fn record_typed(&mut self, change: Change, word_start: bool) {
+        let now = Instant::now();
+        if self.coalesce_into_current(&change, word_start, now) {
+            self.prune();
+        } else {
+            self.record(change);
+        }
+        self.last_typed = Some(now);
+    }
+
+    fn coalesce_into_current(&mut self, change: &Change, word_start: bool, now: Instant) -> bool {
+        let Some(coalesce) = self.coalesce else {
+            return false;
+        };
+        let Some(last_typed) = self.last_typed else {
+            return false;
+        };
+        if self.depth > 0 || (coalesce.word_boundary && word_start) {
+            return false;
+        }
+        if coalesce.window.is_some_and(|window| now.duration_since(last_typed) > window) {
+            return false;
+        }
+
+        let current = self.current;
+        let node = self.node_mut(current);
+        if node.parent.is_none() || !node.children.is_empty() {
+            return false;
+        }
+        let [last] = node.step.changes.as_mut_slice() else {
+            return false;
+        };
+        if !last.removed.is_empty() || !change.removed.is_empty() || last.offset + last.inserted_length() != change.offset {
+            return false;
+        }
+
+        last.inserted.extend_from_slice(&change.inserted);
+        true
+    }
+
+    pub(crate) fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    pub(crate) fn commit(&mut self) {
+        if self.depth == 0 {
+            return;
+        }
+        self.depth -= 1;
+        if self.depth == 0 {
+            self.flush();
+        }
+    }
+
+    pub(crate) fn commit_all(&mut self) {
+        self.depth = 0;
+        self.flush();
+    }
+
+    pub(crate) fn in_transaction(&self) -> bool {
+        self.depth > 0
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        self.node(self.current).parent.is_some() || !self.pending.changes.is_empty()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        self.node(self.current).active_child.is_some()
+    }
+
+    pub(crate) fn undo(&mut self) -> Option<Step> {
+        self.commit_all();
+        let current = self.current;
+        self.node(current).parent?;
+        Some(self.undo_node(current))
+    }
+
+    pub(crate) fn redo(&mut self) -> Option<Step> {
+        self.commit_all();
+        let child = self.node(self.current).active_child?;
+        Some(self.redo_node(child))
+    }
+
+    pub(crate) fn current(&self) -> NodeId {
+        self.current
+    }
+
+    pub(crate) fn pending(&self) -> &Step {
+        &self.pending
+    }
+
+    pub(crate) fn step(&self, id: NodeId) -> &Step {
+        &self.node(id).step
+    }
+
+    pub(crate) fn timeline(&self) -> (Vec<NodeId>, usize) {
+        let mut timeline = self.ancestors(self.current);
+        timeline.reverse();
+        let position = timeline.len() - 1;
+
+        let mut node = self.current;
+        while let Some(child) = self.node(node).active_child {
+            timeline.push(child);
+            node = child;
+        }
+        (timeline, position)
+    }
+
+    pub(crate) fn contains(&self, id: NodeId) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    pub(crate) fn path_to(&self, target: NodeId) -> Option<(Vec<NodeId>, Vec<NodeId>)> {
+        if !self.contains(target) {
+            return None;
+        }
+
+        let ancestors = self.ancestors(target);
+        let mut up = Vec::new();
+        let mut node = self.current;
+        while !ancestors.contains(&node) {
+            up.push(node);
+            node = self.node(node).parent?;
+        }
+
+        let down = ancestors.into_iter().take_while(|&id| id != node).collect::<Vec<_>>();
+        Some((up, down.into_iter().rev().collect()))
+    }
+
+    pub(crate) fn nodes(&self) -> Vec<HistoryNode> {
+        self.nodes
+            .iter()
+            .map(|(&id, node)| HistoryNode {
+                id,
+                parent: node.parent,
+                children: node.children.clone(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn branches(&self) -> Vec<NodeId> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| node.children.is_empty())
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    pub(crate) fn undo_node(&mut self, id: NodeId) -> Step {
+        self.last_typed = None;
+        let parent = self.node(id).parent.expect("root has no step to undo");
+        self.node_mut(parent).active_child = Some(id);
+        self.current = parent;
+        self.node(id).step.clone()
+    }
+
+    pub(crate) fn redo_node(&mut self, id: NodeId) -> Step {
+        self.last_typed = None;
+        if let Some(parent) = self.node(id).parent {
+            self.node_mut(parent).active_child = Some(id);
+        }
+        self.current = id;
+        self.node(id).step.clone()
+    }
+
+    pub(crate) fn applied_since(&self, id: NodeId) -> Option<(Step, Vec<Step>)> {
+        self.node(id).parent?;
+        let ancestors = self.ancestors(self.current);
+        let position = ancestors.iter().position(|&ancestor| ancestor == id)?;
+        let later = ancestors[..position].iter().rev().map(|&later| self.node(later).step.clone()).collect();
+        Some((self.node(id).step.clone(), later))
+    }
+
+    fn ancestors(&self, id: NodeId) -> Vec<NodeId> {
+        let mut ancestors = vec![id];
+        let mut node = id;
+        while let Some(parent) = self.node(node).parent {
+            ancestors.push(parent);
+            node = parent;
+        }
+        ancestors
+    }
+
+    fn flush(&mut self) {
+        if self.pending.changes.is_empty() {
+            return;
+        }
+
+        let step = std::mem::take(&mut self.pending);
+        let parent = self.current;
+        if self.mode == HistoryMode::Linear {
+            for child in std::mem::take(&mut self.node_mut(parent).children) {
+                self.remove_subtree(child);
+            }
+        }
+
+        let id = NodeId(self.next_id);
+        self.next_id += 1;
+        self.nodes.insert(
+            id,
+            Node {
+                parent: Some(parent),
+                children: Vec::new(),
+                active_child: None,
+                step,
+            },
+        );
+
+        let parent_node = self.node_mut(parent);
+        parent_node.children.push(id);
+        parent_node.active_child = Some(id);
+        self.current = id;
+        self.prune();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub(crate) fn estimated_bytes(&self) -> usize {
+        self.nodes.values().map(|node| size_of::<Node>() + node.step.estimated_bytes()).sum()
+    }
+
+    pub(crate) fn prune(&mut self) {
+        while self.over_limit() && self.root != self.current {
+            let kept = *self.ancestors(self.current).iter().rev().nth(1).expect("current descends from root");
+            let old_root = self.nodes.remove(&self.root).expect("root exists");
+            for child in old_root.children.into_iter().filter(|&child| child != kept) {
+                self.remove_subtree(child);
+            }
+
+            let new_root = self.node_mut(kept);
+            new_root.parent = None;
+            new_root.step = Step::default();
+            self.root = kept;
+        }
+    }
+
+    fn over_limit(&self) -> bool {
+        self.limit.max_entries.is_some_and(|max| self.len() > max) || self.limit.max_bytes.is_some_and(|max| self.estimated_bytes() > max)
+    }
+
+    fn remove_subtree(&mut self, id: NodeId) {
+        if let Some(node) = self.nodes.remove(&id) {
+            for child in node.children {
+                self.remove_subtree(child);
+            }
+        }
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[&id]
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.nodes.get_mut(&id).expect("history node exists")
+    }
+}
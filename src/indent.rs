@@ -0,0 +1,136 @@
+use crate::PieceTable;
+use std::borrow::Cow;
+use std::ops::{Range, RangeBounds};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IndentKind {
+    Spaces(usize),
+    Tab,
+}
+
+impl IndentKind {
+    fn unit(self) -> String {
+        match self {
+            IndentKind::Spaces(width) => " ".repeat(width),
+            IndentKind::Tab => "\t".to_string(),
+        }
+    }
+}
+
+impl PieceTable {
+    /// Prefixes every non-blank line in `lines` with one level of indentation, as a single
+    /// undoable step.
+    pub fn indent(&mut self, lines: impl RangeBounds<usize>, kind: IndentKind) {
+        let Range { start: first, end } = self.resolve_lines(lines);
+        let unit = kind.unit();
+        if unit.is_empty() {
+            return;
+        }
+
+        self.transaction(|table| {
+            for line in (first..end).rev() {
+                let range = table.line_range(line);
+                if !table.slice_cow(range.clone()).trim().is_empty() {
+                    table.insert(range.start, &unit);
+                }
+            }
+        });
+    }
+
+    pub fn leading_whitespace_of_line(&self, line: usize) -> Cow<'_, str> {
+        let range = self.line_range(line);
+        self.leading_whitespace(range.start..range.end)
+    }
+
+    /// Returns the indentation a line break inserted at `offset` should carry over: the leading
+    /// whitespace of the current line, cut short if `offset` sits inside it.
+    pub fn auto_indent_for_newline(&self, offset: usize) -> Cow<'_, str> {
+        let offset = self.floor_char_boundary(offset.min(self.length()));
+        let start = self.line_to_byte(self.byte_to_line(offset));
+        self.leading_whitespace(start..offset.max(start))
+    }
+
+    fn leading_whitespace(&self, range: Range<usize>) -> Cow<'_, str> {
+        let text = self.slice_cow(range.clone());
+        let length = text.len() - text.trim_start_matches([' ', '\t']).len();
+        match text {
+            Cow::Borrowed(text) => Cow::Borrowed(&text[..length]),
+            Cow::Owned(_) => self.slice_cow(range.start..range.start + length),
+        }
+    }
+
+    /// Removes up to `width` columns of leading whitespace from every line in `lines`, as a single
+    /// undoable step. A tab advances to the next multiple of `width`, so one tab is one level.
+    pub fn dedent(&mut self, lines: impl RangeBounds<usize>, width: usize) {
+        let Range { start: first, end } = self.resolve_lines(lines);
+        if width == 0 {
+            return;
+        }
+
+        self.transaction(|table| {
+            for line in (first..end).rev() {
+                let range = table.line_range(line);
+                let mut columns = 0;
+                let mut length = 0;
+                for ch in table.slice_cow(range.clone()).chars() {
+                    match ch {
+                        ' ' => columns += 1,
+                        '\t' => columns = (columns / width + 1) * width,
+                        _ => break,
+                    }
+                    length += 1;
+                    if columns >= width {
+                        break;
+                    }
+                }
+                table.delete(range.start, length);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indent() {
+        let mut piece_table = PieceTable::from("fn f() {\n\nx\r\n}");
+        piece_table.indent(1..3, IndentKind::Spaces(4));
+        assert_eq!("fn f() {\n\n    x\r\n}", piece_table.text());
+        piece_table.indent(.., IndentKind::Tab);
+        assert_eq!("\tfn f() {\n\n\t    x\r\n\t}", piece_table.text());
+
+        assert_eq!(2, piece_table.history_len());
+        assert!(piece_table.undo());
+        assert_eq!("fn f() {\n\n    x\r\n}", piece_table.text());
+    }
+
+    #[test]
+    fn test_dedent() {
+        let mut piece_table = PieceTable::from("      six\n\t tab\n  \tmixed\nnone");
+        piece_table.dedent(.., 4);
+        assert_eq!("  six\n tab\nmixed\nnone", piece_table.text());
+        piece_table.dedent(0..2, 4);
+        assert_eq!("six\ntab\nmixed\nnone", piece_table.text());
+
+        assert_eq!(2, piece_table.history_len());
+        assert!(piece_table.undo());
+        assert_eq!("  six\n tab\nmixed\nnone", piece_table.text());
+    }
+
+    #[test]
+    fn test_auto_indent() {
+        let mut piece_table = PieceTable::from("fn f() {\n\t  body();\n}");
+        piece_table.insert(10, " ");
+        assert_eq!("\t   ", piece_table.leading_whitespace_of_line(1));
+        assert_eq!("", piece_table.leading_whitespace_of_line(0));
+        assert_eq!("", piece_table.leading_whitespace_of_line(7));
+
+        assert_eq!("\t   ", piece_table.auto_indent_for_newline(20));
+        assert_eq!("\t ", piece_table.auto_indent_for_newline(11));
+        assert_eq!("", piece_table.auto_indent_for_newline(5));
+        assert_eq!("", piece_table.auto_indent_for_newline(100));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{Piece, PieceTable};
+use std::ops::{Range, RangeBounds};
+use std::slice;
+use std::str;
+
+#[derive(Debug, Clone)]
+pub struct Chars<'a> {
+    table: &'a PieceTable,
+    pieces: slice::Iter<'a, Piece>,
+    front: str::Chars<'a>,
+    back: str::Chars<'a>,
+}
+
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(ch) = self.front.next() {
+                return Some(ch);
+            }
+            match self.pieces.next() {
+                Some(piece) => self.front = self.table.piece_text(piece).chars(),
+                None => return self.back.next(),
+            }
+        }
+    }
+}
+
+impl DoubleEndedIterator for Chars<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(ch) = self.back.next_back() {
+                return Some(ch);
+            }
+            match self.pieces.next_back() {
+                Some(piece) => self.back = self.table.piece_text(piece).chars(),
+                None => return self.front.next_back(),
+            }
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a PieceTable {
+    type Item = char;
+    type IntoIter = Chars<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chars()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CharsAt<'a> {
+    table: &'a PieceTable,
+    index: usize,
+    local: usize,
+    offset: usize,
+}
+
+impl CharsAt<'_> {
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Steps back over the char before the current offset, the counterpart of `next`.
+    pub fn prev(&mut self) -> Option<char> {
+        loop {
+            if self.local > 0 {
+                let text = self.table.piece_text(&self.table.pieces[self.index]);
+                let ch = text[..self.local].chars().next_back()?;
+                self.local -= ch.len_utf8();
+                self.offset -= ch.len_utf8();
+                return Some(ch);
+            }
+            self.index = self.index.checked_sub(1)?;
+            self.local = self.table.pieces[self.index].length;
+        }
+    }
+}
+
+impl Iterator for CharsAt<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let piece = self.table.pieces.get(self.index)?;
+            if let Some(ch) = self.table.piece_text(piece)[self.local..].chars().next() {
+                self.local += ch.len_utf8();
+                self.offset += ch.len_utf8();
+                return Some(ch);
+            }
+            self.index += 1;
+            self.local = 0;
+        }
+    }
+}
+
+impl PieceTable {
+    pub fn chunks(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.pieces.iter().map(|piece| self.piece_text(piece))
+    }
+
+    pub fn chunks_in_range(&self, range: impl RangeBounds<usize>) -> impl DoubleEndedIterator<Item = &str> {
+        let Range { start, end } = self.resolve_range(range);
+        let (pieces, first_start, last_start) = match (self.piece_at(start), self.piece_at(end.wrapping_sub(1))) {
+            (Some((_, first, first_start)), Some((_, last, last_start))) if start < end => (&self.pieces[first..=last], first_start, last_start),
+            _ => (&self.pieces[..0], 0, 0),
+        };
+
+        let last = pieces.len().saturating_sub(1);
+        pieces.iter().enumerate().map(move |(index, piece)| {
+            let local_start = if index == 0 { start - first_start } else { 0 };
+            let local_end = if index == last { end - last_start } else { piece.length };
+            &self.piece_text(piece)[local_start..local_end]
+        })
+    }
+
+    /// Returns an iterator positioned at `offset`, floored to a char boundary: `next` reads the
+    /// chars after it and `prev` the chars before it.
+    pub fn chars_at(&self, offset: usize) -> CharsAt<'_> {
+        let offset = self.floor_char_boundary(offset);
+        let (index, local) = match self.piece_at(offset) {
+            Some((_, index, running_total)) => (index, offset - running_total),
+            None => (self.pieces.len(), 0),
+        };
+        CharsAt {
+            table: self,
+            index,
+            local,
+            offset,
+        }
+    }
+
+    pub fn bytes(&self) -> impl DoubleEndedIterator<Item = u8> + '_ {
+        self.chunks().flat_map(str::bytes)
+    }
+
+    pub fn chars(&self) -> Chars<'_> {
+        Chars {
+            table: self,
+            pieces: self.pieces.iter(),
+            front: "".chars(),
+            back: "".chars(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes() {
+        let mut piece_table = PieceTable::from("héllo");
+        piece_table.insert(3, "-");
+        piece_table.push('!');
+
+        assert_eq!(piece_table.text().into_bytes(), piece_table.bytes().collect::<Vec<u8>>());
+        assert_eq!(0, PieceTable::default().bytes().count());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut piece_table = PieceTable::from("one three");
+        piece_table.insert(4, "two ");
+
+        assert_eq!(vec!["one ", "two ", "three"], piece_table.chunks().collect::<Vec<&str>>());
+        assert_eq!(piece_table.text(), piece_table.chunks().collect::<String>());
+    }
+
+    #[test]
+    fn test_chunks_in_range() {
+        let mut piece_table = PieceTable::from("one three");
+        piece_table.insert(4, "two ");
+
+        assert_eq!(vec!["ne ", "two ", "th"], piece_table.chunks_in_range(1..10).collect::<Vec<&str>>());
+        assert_eq!(vec!["w"], piece_table.chunks_in_range(5..=5).collect::<Vec<&str>>());
+        assert_eq!(vec!["two ", "three"], piece_table.chunks_in_range(4..).collect::<Vec<&str>>());
+        assert_eq!(0, piece_table.chunks_in_range(6..6).count());
+        assert_eq!(0, piece_table.chunks_in_range(20..).count());
+    }
+
+    #[test]
+    fn test_reverse_iteration() {
+        let mut piece_table = PieceTable::from("añb");
+        piece_table.push_str("çd");
+
+        assert_eq!("dçbña", piece_table.chars().rev().collect::<String>());
+        assert_eq!(vec![b'd', 0xa7, 0xc3], piece_table.bytes().rev().take(3).collect::<Vec<u8>>());
+        assert_eq!(vec!["çd", "añb"], piece_table.chunks().rev().collect::<Vec<&str>>());
+        assert_eq!(vec!["ç", "ñb"], piece_table.chunks_in_range(1..6).rev().collect::<Vec<&str>>());
+
+        let mut chars = piece_table.chars();
+        assert_eq!((Some('a'), Some('d')), (chars.next(), chars.next_back()));
+        assert_eq!("ñbç", chars.collect::<String>());
+    }
+
+    #[test]
+    fn test_chars_at() {
+        let mut piece_table = PieceTable::from("añb");
+        piece_table.push_str("çd");
+
+        let mut chars = piece_table.chars_at(2);
+        assert_eq!(1, chars.offset());
+        assert_eq!("ñbçd", chars.by_ref().collect::<String>());
+        assert_eq!(Some('d'), chars.prev());
+        assert_eq!(Some('ç'), chars.prev());
+        assert_eq!(4, chars.offset());
+        assert_eq!(Some('ç'), chars.next());
+
+        let mut chars = piece_table.chars_at(4);
+        assert_eq!(
+            (Some('b'), Some('ñ'), Some('a'), None),
+            (chars.prev(), chars.prev(), chars.prev(), chars.prev())
+        );
+        assert_eq!(Some('a'), chars.next());
+        assert_eq!(None, PieceTable::default().chars_at(0).prev());
+    }
+
+    #[test]
+    fn test_into_iterator() {
+        let mut piece_table = PieceTable::from("a-b");
+        piece_table.insert(2, "c-");
+
+        let mut letters = String::new();
+        for ch in &piece_table {
+            if ch != '-' {
+                letters.push(ch);
+            }
+        }
+        assert_eq!("acb", letters);
+        assert_eq!(3, (&piece_table).into_iter().filter(char::is_ascii_alphabetic).count());
+    }
+}
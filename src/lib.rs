@@ -1,195 +1,796 @@
+#[cfg(feature = "automerge")]
+mod automerge_text;
+mod brackets;
+mod case;
+mod chars;
+mod crdt;
+mod cursor;
+mod delta;
+mod diff;
+mod dirty;
+mod drain;
+mod edit;
+mod error;
+#[cfg(feature = "fuzzy")]
+mod fuzzy;
+#[cfg(feature = "unicode")]
+mod graphemes;
+mod hash;
+mod history;
+mod indent;
+mod iter;
+mod line_ending;
+mod lines;
+mod lsp;
+mod merge;
+#[cfg(feature = "aho-corasick")]
+mod multi_search;
+mod objects;
+mod observe;
+mod ot;
+mod output;
+mod paragraphs;
+mod patch;
+mod ranges;
+#[cfg(feature = "regex")]
+mod regex;
+mod retain;
+mod search;
+mod search_session;
+mod snapshot;
+mod timeline;
+mod widths;
+#[cfg(feature = "unicode")]
+mod words;
+mod wrap;
+#[cfg(feature = "yrs")]
+mod yjs;
+
+#[cfg(feature = "automerge")]
+pub use automerge_text::AutomergeText;
+pub use case::CaseTransform;
+pub use crdt::{CharId, CrdtOp, CrdtTable};
+pub use cursor::Cursor;
+pub use delta::DeltaOp;
+pub use dirty::StaleRevision;
+pub use drain::Drain;
+pub use edit::{Edit, Overlap, OverlappingEdits};
+pub use error::PieceTableError;
+#[cfg(feature = "fuzzy")]
+pub use fuzzy::FuzzyMatch;
+#[cfg(feature = "unicode")]
+pub use graphemes::Graphemes;
+pub use history::{Coalesce, HistoryLimit, HistoryMode, HistoryNode, NodeId};
+pub use indent::IndentKind;
+pub use iter::{Chars, CharsAt};
+pub use line_ending::LineEnding;
+pub use lsp::{LspPosition, LspRange, PositionEncoding};
+pub use merge::{detect_conflicts, Conflict, EditConflict};
+pub use observe::{ChangeInfo, ObserverId};
+pub use ot::{transform, transform_batches};
+pub use output::WithLineEnding;
+pub use patch::Patch;
+#[cfg(feature = "regex")]
+pub use regex::RegexError;
+pub use search::{CaseMatching, SearchMatch, Split};
+pub use search_session::SearchSession;
+pub use snapshot::Snapshot;
+pub use timeline::{Revision, Revisions};
+pub use wrap::WrapMode;
+#[cfg(feature = "yrs")]
+pub use yjs::{YjsError, YjsText};
+
+use dirty::ChangeLog;
+use hash::HashCache;
+use history::{Change, History, Step};
+use lines::LineBreaks;
+use observe::Observers;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::convert::Infallible;
 use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Range, RangeBounds};
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+use widths::LineWidths;
 
 #[derive(Debug, Copy, PartialOrd, PartialEq, Clone, Ord, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Buffer {
     Original,
     Add,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
     buffer: Buffer,
     length: usize,
     offset: usize,
+    line_breaks: usize,
 }
 
 impl Piece {
-    fn new(buffer: Buffer, length: usize, offset: usize) -> Self {
-        Piece { buffer, length, offset }
+    fn new(buffer: Buffer, length: usize, offset: usize, line_breaks: usize) -> Self {
+        Piece {
+            buffer,
+            length,
+            offset,
+            line_breaks,
+        }
+    }
+
+    fn text<'a>(&self, original: &'a str, add: &'a str) -> &'a str {
+        match self.buffer {
+            Buffer::Original => &original[self.offset..self.offset + self.length],
+            Buffer::Add => &add[self.offset..self.offset + self.length],
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PieceTable {
-    original: String,
-    add: String,
+    original: Arc<str>,
+    add: Arc<String>,
     pieces: Vec<Piece>,
+    piece_starts: Vec<usize>,
+    piece_lines: Vec<usize>,
+    length: usize,
+    history: History,
+    recording: Option<Vec<Edit>>,
+    changes: ChangeLog,
+    line_breaks: LineBreaks,
+    line_widths: LineWidths,
+    insert_line_ending: Option<LineEnding>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    add_ancestry: Vec<(Weak<String>, usize)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observers: Observers,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    content_hash: HashCache,
 }
 
 impl PieceTable {
     pub fn new(original: String) -> Self {
-        let original_piece = Piece::new(Buffer::Original, original.len(), 0);
+        PieceTable::with_original(Arc::from(original))
+    }
+
+    fn with_original(original: Arc<str>) -> Self {
+        let line_breaks = LineBreaks::new(&original);
+        let line_widths = LineWidths::new([&*original]);
+        let original_piece = line_breaks.piece(Buffer::Original, original.len(), 0);
         let pieces = vec![original_piece];
-        let add = String::new();
+        let add = Arc::new(String::new());
+        let history = History::default();
 
-        PieceTable { original, add, pieces }
+        PieceTable {
+            length: original.len(),
+            original,
+            add,
+            piece_starts: vec![0],
+            piece_lines: vec![0],
+            pieces,
+            history,
+            recording: None,
+            changes: ChangeLog::default(),
+            line_breaks,
+            line_widths,
+            insert_line_ending: None,
+            add_ancestry: Vec::new(),
+            observers: Observers::default(),
+            content_hash: HashCache::default(),
+        }
     }
 
     pub fn char_at(&self, offset: usize) -> Option<char> {
-        if let Some((piece, _index, running_total)) = self.piece_at(offset) {
-            let content = match piece.buffer {
-                Buffer::Original => self.original.chars().nth(piece.offset + offset - running_total),
-                Buffer::Add => self.add.chars().nth(piece.offset + offset - running_total),
-            };
-            return content;
-        }
-        None
+        let (piece, _index, running_total) = self.piece_at(offset)?;
+        let text = self.piece_text(&piece);
+        text.get(offset - running_total..)?.chars().next()
     }
 
     pub fn piece_at(&self, offset: usize) -> Option<(Piece, usize, usize)> {
-        let mut running_total = 0;
+        if offset >= self.length {
+            return None;
+        }
+        // The last piece starting at or before `offset` is never empty, since it reaches past it.
+        let index = self.piece_starts.partition_point(|&start| start <= offset) - 1;
+        Some((self.pieces[index], index, self.piece_starts[index]))
+    }
 
-        for (index, piece) in self.pieces.iter().enumerate() {
-            let offset_start = running_total;
-            let offset_end = piece.length + running_total;
-            let range = offset_start..offset_end;
+    // Where to start scanning for `offset`: a piece that starts at or before it.
+    fn piece_hint(&self, offset: usize) -> (usize, usize) {
+        let index = self.piece_starts.partition_point(|&start| start <= offset).saturating_sub(1);
+        (index, self.piece_starts.get(index).copied().unwrap_or(0))
+    }
 
-            if range.contains(&offset) {
-                return Some((piece.clone(), index, running_total));
-            }
-            running_total += piece.length;
+    // Brings the start offsets and line numbers up to date from piece `from` on, after the pieces
+    // there changed.
+    fn reindex_pieces(&mut self, from: usize) {
+        self.piece_starts.truncate(from);
+        self.piece_lines.truncate(from);
+        let (mut start, mut line) = match from.checked_sub(1) {
+            Some(previous) => (
+                self.piece_starts[previous] + self.pieces[previous].length,
+                self.piece_lines[previous] + self.pieces[previous].line_breaks,
+            ),
+            None => (0, 0),
+        };
+        for piece in &self.pieces[from..] {
+            self.piece_starts.push(start);
+            self.piece_lines.push(line);
+            start += piece.length;
+            line += piece.line_breaks;
         }
-        None
     }
 
     pub fn insert(&mut self, offset: usize, content: &str) {
-        let total_length = self.length();
+        if content.is_empty() {
+            return;
+        }
 
+        let offset = self.floor_char_boundary(offset);
+        let content = self.normalize_insert(content);
+        let add_piece = self.push_add(&content);
+
+        let change = self.replace_pieces(offset, 0, vec![add_piece]);
+        self.record_insert(change, &content);
+    }
+
+    pub fn push_str(&mut self, content: &str) {
         if content.is_empty() {
             return;
         }
 
-        if offset == 0 {
-            let add_piece = Piece::new(Buffer::Add, content.len(), self.add.len());
-            self.pieces.insert(0, add_piece);
-            self.add.push_str(content);
-        } else if offset >= total_length {
-            let add_piece = Piece::new(Buffer::Add, content.len(), self.add.len());
-            self.pieces.push(add_piece);
-            self.add.push_str(content);
-        } else {
-            if let Some((piece_to_split, index, total)) = self.piece_at(offset) {
-                let mut index = index;
-
-                let remaining_length = offset.checked_sub(total).unwrap();
-                let remaining_offset = piece_to_split.offset;
-                let remaining = Piece::new(piece_to_split.buffer, remaining_length, remaining_offset);
-
-                let new_length = piece_to_split.length.checked_sub(remaining_length).unwrap();
-                let new_offset = remaining_length + remaining_offset;
-                let new = Piece::new(piece_to_split.buffer, new_length, new_offset);
-
-                let re_add_org = remaining_length > 0;
-
-                let new_add = Piece::new(Buffer::Add, content.len(), self.add.len());
-                self.pieces.insert(index + 1, new_add);
-                self.add.push_str(content);
-
-                if re_add_org {
-                    self.pieces[index] = remaining;
-                } else {
-                    self.pieces.remove(index);
-                    index -= 1;
-                }
-
-                self.pieces.insert(index + 2, new);
+        let offset = self.length();
+        let content = self.normalize_insert(content);
+        let add_piece = self.push_add(&content);
+        match self.pieces.last_mut() {
+            Some(last) if last.buffer == Buffer::Add && last.offset + last.length == add_piece.offset => {
+                last.length += add_piece.length;
+                last.line_breaks += add_piece.line_breaks;
             }
+            _ => {
+                self.pieces.push(add_piece);
+                self.reindex_pieces(self.pieces.len() - 1);
+            }
+        }
+
+        let change = Change {
+            offset,
+            removed: Vec::new(),
+            inserted: vec![add_piece],
+        };
+        self.track(&change);
+        self.record_insert(change, &content);
+    }
+
+    fn record_insert(&mut self, change: Change, content: &str) {
+        let mut chars = content.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => self.history.record_typed(change, ch.is_whitespace()),
+            _ => self.history.record(change),
         }
     }
 
+    pub fn insert_char(&mut self, offset: usize, ch: char) {
+        self.insert(offset, ch.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let ch = self.chars().next_back()?;
+        self.delete(self.length() - ch.len_utf8(), ch.len_utf8());
+        Some(ch)
+    }
+
     pub fn delete(&mut self, offset: usize, length: usize) {
         let total_length = self.length();
+        if offset >= total_length || length == 0 {
+            return;
+        }
+
+        let end = self.ceil_char_boundary(offset.saturating_add(length));
+        let offset = self.floor_char_boundary(offset);
+        self.edit(offset, end - offset, Vec::new());
+    }
+
+    pub fn truncate(&mut self, new_length: usize) {
+        let length = self.length();
+        if new_length < length {
+            self.delete(new_length, length - new_length);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        let length = self.length();
+        self.delete(0, length);
+    }
 
-        if total_length < length && offset == 0 {
-            self.pieces.clear();
+    pub fn delete_ret(&mut self, offset: usize, length: usize) -> String {
+        let removed = self.slice(offset..offset.saturating_add(length));
+        self.delete(offset, length);
+        removed
+    }
+
+    pub fn splice(&mut self, range: impl RangeBounds<usize>, content: &str) -> String {
+        let Range { start, end } = self.resolve_range(range);
+        if start == end && content.is_empty() {
+            return String::new();
+        }
+
+        let content = self.normalize_insert(content);
+        let add_piece = self.push_add(&content);
+        let change = self.replace_pieces(start, end - start, vec![add_piece]);
+        let removed = change.removed.iter().map(|piece| self.piece_text(piece)).collect();
+        self.history.record(change);
+        removed
+    }
+
+    pub fn replace(&mut self, range: impl RangeBounds<usize>, content: &str) {
+        let Range { start, end } = self.resolve_range(range);
+        if start == end && content.is_empty() {
             return;
         }
 
-        let delete_range = offset..(offset + length);
+        let content = self.normalize_insert(content);
+        let add_piece = self.push_add(&content);
+        self.edit(start, end - start, vec![add_piece]);
+    }
 
-        let mut running_total = 0;
-        let mut split = false;
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo() {
+            Some(step) => {
+                self.revert_step(&step);
+                true
+            }
+            None => false,
+        }
+    }
 
-        let _ = &self.pieces.retain_mut(|piece| {
-            let piece_start = running_total;
-            let piece_end = piece.length + running_total;
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo() {
+            Some(step) => {
+                self.apply_step(&step);
+                true
+            }
+            None => false,
+        }
+    }
 
-            running_total += piece.length;
+    pub fn selective_undo(&mut self, node: NodeId) -> bool {
+        self.history.commit_all();
+        if !self.history.contains(node) {
+            return false;
+        }
+        let Some((step, later)) = self.history.applied_since(node) else {
+            return false;
+        };
 
-            if delete_range.contains(&piece_start) && delete_range.contains(&piece_end) {
+        let mut changes: Vec<Change> = step.changes.iter().rev().map(Change::inverse).collect();
+        for change in later.iter().flat_map(|step| &step.changes) {
+            if !history::rebase(&mut changes, change) {
                 return false;
-            } else if delete_range.contains(&piece_start) {
-                let diff = delete_range.end - piece_start;
-                if piece.length - diff == 0 {
-                    return false;
-                }
-                piece.length = piece.length - diff;
-                piece.offset = piece.offset + diff;
-                return true;
-            } else if delete_range.contains(&piece_end) {
-                piece.length = delete_range.start;
-                return true;
-            } else if delete_range.start > piece_start && delete_range.end < piece_end {
-                split = true;
-                return true;
             }
-            true
-        });
+        }
 
-        if split {
-            if let Some((piece_to_split, index, total)) = self.piece_at(offset) {
-                if piece_to_split.length == length {
-                    self.pieces.remove(index);
-                } else if piece_to_split.length > length {
-                    let new_length = piece_to_split.length.checked_sub(offset + length).unwrap();
-                    let new_offset = offset + length + piece_to_split.offset;
-                    let new = Piece::new(piece_to_split.buffer, new_length, new_offset);
-
-                    let remaining_length = offset.checked_sub(total).unwrap();
-                    let remaining_offset = piece_to_split.offset;
-                    let remaining = Piece::new(piece_to_split.buffer, remaining_length, remaining_offset);
-
-                    self.pieces[index] = remaining;
-                    self.pieces.insert(index + 1, new);
-                }
+        self.transaction(|table| {
+            for change in changes {
+                table.edit(change.offset, change.removed_length(), change.inserted);
             }
+        });
+        true
+    }
+
+    pub fn checkpoint(&mut self, name: &str) {
+        self.history.commit_all();
+        let current = self.history.current();
+        self.history.checkpoints.insert(name.to_string(), current);
+    }
+
+    pub fn revert_to_checkpoint(&mut self, name: &str) -> bool {
+        match self.history.checkpoints.get(name) {
+            Some(&node) => self.jump_to(node),
+            None => false,
         }
     }
 
-    pub fn length(&self) -> usize {
-        let mut length = 0;
-        for piece in &self.pieces {
-            length += piece.length;
+    pub fn remove_checkpoint(&mut self, name: &str) -> bool {
+        self.history.checkpoints.remove(name).is_some()
+    }
+
+    pub fn history_mode(&self) -> HistoryMode {
+        self.history.mode
+    }
+
+    pub fn set_history_mode(&mut self, mode: HistoryMode) {
+        self.history.mode = mode;
+    }
+
+    pub fn coalescing(&self) -> Option<Coalesce> {
+        self.history.coalesce
+    }
+
+    pub fn set_coalescing(&mut self, coalesce: Option<Coalesce>) {
+        self.history.coalesce = coalesce;
+    }
+
+    pub fn history_limit(&self) -> HistoryLimit {
+        self.history.limit
+    }
+
+    pub fn set_history_limit(&mut self, limit: HistoryLimit) {
+        self.history.limit = limit;
+        self.history.prune();
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn history_memory(&self) -> usize {
+        self.history.estimated_bytes()
+    }
+
+    pub fn current_node(&self) -> NodeId {
+        self.history.current()
+    }
+
+    pub fn history_nodes(&self) -> Vec<HistoryNode> {
+        self.history.nodes()
+    }
+
+    pub fn history_branches(&self) -> Vec<NodeId> {
+        self.history.branches()
+    }
+
+    pub fn jump_to(&mut self, node: NodeId) -> bool {
+        self.history.commit_all();
+        let Some((up, down)) = self.history.path_to(node) else {
+            return false;
+        };
+
+        for id in up {
+            let step = self.history.undo_node(id);
+            self.revert_step(&step);
+        }
+        for id in down {
+            let step = self.history.redo_node(id);
+            self.apply_step(&step);
         }
-        length
+        true
+    }
+
+    pub fn begin_transaction(&mut self) {
+        self.history.begin();
+    }
+
+    pub fn commit(&mut self) {
+        self.history.commit();
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.history.in_transaction()
+    }
+
+    pub fn transaction<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut PieceTable) -> R,
+    {
+        self.begin_transaction();
+        let result = f(self);
+        self.commit();
+        result
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    fn push_add(&mut self, content: &str) -> Piece {
+        let offset = self.add.len();
+        if Arc::get_mut(&mut self.add).is_none() {
+            self.add_ancestry.retain(|(buffer, _)| buffer.strong_count() > 0);
+            self.add_ancestry.push((Arc::downgrade(&self.add), offset));
+        }
+        Arc::make_mut(&mut self.add).push_str(content);
+        self.line_breaks.push_add(offset, content);
+        self.line_breaks.piece(Buffer::Add, content.len(), offset)
+    }
+
+    fn edit(&mut self, offset: usize, length: usize, inserted: Vec<Piece>) {
+        let change = self.replace_pieces(offset, length, inserted);
+        self.history.record(change);
+    }
+
+    fn apply_step(&mut self, step: &Step) {
+        for change in &step.changes {
+            self.apply_change(change);
+        }
+    }
+
+    fn revert_step(&mut self, step: &Step) {
+        for change in step.changes.iter().rev() {
+            self.apply_change(&change.inverse());
+        }
+    }
+
+    fn apply_change(&mut self, change: &Change) {
+        self.replace_pieces(change.offset, change.removed_length(), change.inserted.clone());
+    }
+
+    fn replace_pieces(&mut self, offset: usize, length: usize, inserted: Vec<Piece>) -> Change {
+        self.replace_pieces_at(self.piece_hint(offset), offset, length, inserted)
+    }
+
+    // Like `replace_pieces`, but scans for `offset` from `hint`, the index and start of a piece
+    // that starts at or before it, instead of looking the piece up.
+    fn replace_pieces_at(&mut self, hint: (usize, usize), offset: usize, length: usize, inserted: Vec<Piece>) -> Change {
+        let inserted: Vec<Piece> = inserted.into_iter().filter(|piece| piece.length > 0).collect();
+        let removed = splice_pieces_from(&mut self.pieces, &self.line_breaks, hint, offset, length, &inserted);
+        // Splitting and merging never move the start of the piece before the hint.
+        self.reindex_pieces(hint.0.saturating_sub(1));
+
+        let change = Change { offset, removed, inserted };
+        self.track(&change);
+        change
+    }
+
+    fn track(&mut self, change: &Change) {
+        self.length = self.length - change.removed_length() + change.inserted_length();
+        self.changes.record(change.offset, change.removed_length(), change.inserted_length());
+        let removed_breaks = change.removed.iter().map(|piece| piece.line_breaks).sum();
+        let inserted_breaks = change.inserted.iter().map(|piece| piece.line_breaks).sum();
+        self.track_line_widths(change.offset, removed_breaks, inserted_breaks);
+        if self.recording.is_some() {
+            self.record_edits(change);
+        }
+        if !self.observers.is_empty() {
+            self.notify_observers(change);
+        }
+    }
+
+    fn record_edits(&mut self, change: &Change) {
+        let mut edits = Vec::new();
+        if !change.removed.is_empty() {
+            let range = change.offset..change.offset + change.removed_length();
+            edits.push(Edit::Delete { range });
+        }
+        if !change.inserted.is_empty() {
+            let text = change.inserted.iter().map(|piece| self.piece_text(piece)).collect();
+            edits.push(Edit::Insert { offset: change.offset, text });
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.extend(edits);
+        }
+    }
+
+    fn notify_observers(&self, change: &Change) {
+        let info = ChangeInfo {
+            offset: change.offset,
+            deleted: change.removed_length(),
+            inserted: change.inserted.iter().map(|piece| self.piece_text(piece)).collect(),
+            revision: self.revision(),
+        };
+        self.observers.notify(&info);
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length() == 0
     }
 
     pub fn text(&self) -> String {
-        let mut result = String::new();
+        self.chunks().collect()
+    }
+
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> String {
+        self.chunks_in_range(range).collect()
+    }
+
+    pub(crate) fn slice_cow(&self, range: Range<usize>) -> Cow<'_, str> {
+        match self.piece_at(range.start) {
+            Some((piece, _, running_total)) if range.end <= running_total + piece.length => {
+                Cow::Borrowed(&self.piece_text(&piece)[range.start - running_total..range.end - running_total])
+            }
+            _ => Cow::Owned(self.slice(range)),
+        }
+    }
+
+    pub(crate) fn pieces_in(&self, range: Range<usize>) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+        let mut running_total = 0;
         for piece in &self.pieces {
-            let content = match piece.buffer {
-                Buffer::Original => &self.original[piece.offset..piece.offset + piece.length],
-                Buffer::Add => &self.add[piece.offset..piece.offset + piece.length],
-            };
-            result.push_str(content);
+            let start = range.start.clamp(running_total, running_total + piece.length);
+            let end = range.end.clamp(running_total, running_total + piece.length);
+            if start < end {
+                pieces.push(self.line_breaks.piece(piece.buffer, end - start, piece.offset + start - running_total));
+            }
+            running_total += piece.length;
         }
-        result
+        pieces
+    }
+
+    fn piece_text(&self, piece: &Piece) -> &str {
+        piece.text(&self.original, &self.add)
+    }
+}
+
+fn splice_pieces(pieces: &mut Vec<Piece>, line_breaks: &LineBreaks, offset: usize, length: usize, inserted: &[Piece]) -> Vec<Piece> {
+    splice_pieces_from(pieces, line_breaks, (0, 0), offset, length, inserted)
+}
+
+/// Like `splice_pieces`, scanning from `hint`, the index and start offset of a piece that starts
+/// at or before `offset`.
+fn splice_pieces_from(
+    pieces: &mut Vec<Piece>,
+    line_breaks: &LineBreaks,
+    hint: (usize, usize),
+    offset: usize,
+    length: usize,
+    inserted: &[Piece],
+) -> Vec<Piece> {
+    let start = split_at(pieces, line_breaks, hint, offset);
+    let end = split_at(pieces, line_breaks, (start, offset), offset + length);
+    let removed = pieces.splice(start..end, inserted.iter().copied()).collect();
+
+    merge_at(pieces, start + inserted.len());
+    merge_at(pieces, start);
+    removed
+}
+
+fn split_at(pieces: &mut Vec<Piece>, line_breaks: &LineBreaks, (from, mut running_total): (usize, usize), offset: usize) -> usize {
+    for index in from..pieces.len() {
+        let piece = pieces[index];
+        if offset == running_total {
+            return index;
+        }
+        if offset < running_total + piece.length {
+            let left_length = offset - running_total;
+            let left_breaks = line_breaks.count(piece.buffer, piece.offset, left_length);
+            let right = Piece::new(
+                piece.buffer,
+                piece.length - left_length,
+                piece.offset + left_length,
+                piece.line_breaks - left_breaks,
+            );
+            pieces[index].length = left_length;
+            pieces[index].line_breaks = left_breaks;
+            pieces.insert(index + 1, right);
+            return index + 1;
+        }
+        running_total += piece.length;
+    }
+    pieces.len()
+}
+
+fn merge_at(pieces: &mut Vec<Piece>, index: usize) {
+    if index == 0 || index >= pieces.len() {
+        return;
+    }
+
+    let left = pieces[index - 1];
+    let right = pieces[index];
+    if left.buffer == right.buffer && left.offset + left.length == right.offset {
+        pieces[index - 1].length += right.length;
+        pieces[index - 1].line_breaks += right.line_breaks;
+        pieces.remove(index);
+    }
+}
+
+impl PartialEq for PieceTable {
+    fn eq(&self, other: &Self) -> bool {
+        snapshot::same_text((&self.original, &self.add, &self.pieces), (&other.original, &other.add, &other.pieces))
+    }
+}
+
+impl Eq for PieceTable {}
+
+impl PartialOrd for PieceTable {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PieceTable {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.text().cmp(&other.text())
     }
 }
 
 impl Display for PieceTable {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.text())
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl Default for PieceTable {
+    fn default() -> Self {
+        PieceTable::with_original(Arc::from(""))
+    }
+}
+
+impl From<&str> for PieceTable {
+    fn from(original: &str) -> Self {
+        PieceTable::with_original(Arc::from(original))
+    }
+}
+
+impl From<String> for PieceTable {
+    fn from(original: String) -> Self {
+        PieceTable::new(original)
+    }
+}
+
+impl FromStr for PieceTable {
+    type Err = Infallible;
+
+    fn from_str(original: &str) -> Result<Self, Self::Err> {
+        Ok(PieceTable::from(original))
+    }
+}
+
+impl Add<&str> for PieceTable {
+    type Output = PieceTable;
+
+    fn add(mut self, content: &str) -> Self::Output {
+        self.push_str(content);
+        self
+    }
+}
+
+impl Add<PieceTable> for PieceTable {
+    type Output = PieceTable;
+
+    fn add(mut self, other: PieceTable) -> Self::Output {
+        self.append_table(other);
+        self
+    }
+}
+
+impl AddAssign<&str> for PieceTable {
+    fn add_assign(&mut self, content: &str) {
+        self.push_str(content);
+    }
+}
+
+impl Extend<char> for PieceTable {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let content: String = iter.into_iter().collect();
+        self.push_str(&content);
+    }
+}
+
+impl<'a> Extend<&'a str> for PieceTable {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        let content: String = iter.into_iter().collect();
+        self.push_str(&content);
+    }
+}
+
+impl FromIterator<char> for PieceTable {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        PieceTable::new(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<String> for PieceTable {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        PieceTable::new(iter.into_iter().collect())
     }
 }
 
@@ -209,4 +810,375 @@ mod tests {
         assert_eq!("Lorem ipsum dolor sit amet", piece_table.text());
         assert_eq!('o', piece_table.char_at(15).unwrap());
     }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut piece_table = PieceTable::new("ipsum amet".to_string());
+
+        piece_table.insert(0, "Lorem ");
+        piece_table.insert(12, "sit ");
+        piece_table.delete(0, 6);
+        assert_eq!("ipsum sit amet", piece_table.text());
+
+        assert!(piece_table.undo());
+        assert_eq!("Lorem ipsum sit amet", piece_table.text());
+        assert!(piece_table.undo());
+        assert!(piece_table.undo());
+        assert_eq!("ipsum amet", piece_table.text());
+        assert!(!piece_table.undo());
+
+        assert!(piece_table.redo());
+        assert_eq!("Lorem ipsum amet", piece_table.text());
+
+        piece_table.insert(16, "!");
+        assert!(!piece_table.redo());
+        assert_eq!("Lorem ipsum amet!", piece_table.text());
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut piece_table = PieceTable::new("a b a b".to_string());
+
+        piece_table.transaction(|table| {
+            table.delete(4, 1);
+            table.insert(4, "c");
+            table.delete(0, 1);
+            table.insert(0, "c");
+        });
+        piece_table.begin_transaction();
+        piece_table.insert(7, "!");
+        piece_table.insert(8, "!");
+        piece_table.commit();
+        assert_eq!("c b c b!!", piece_table.text());
+
+        assert!(piece_table.undo());
+        assert_eq!("c b c b", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("a b a b", piece_table.text());
+        assert!(piece_table.redo());
+        assert_eq!("c b c b", piece_table.text());
+    }
+
+    #[test]
+    fn test_undo_tree() {
+        let mut piece_table = PieceTable::new("abc".to_string());
+        piece_table.set_history_mode(HistoryMode::Tree);
+
+        piece_table.insert(3, "d");
+        let first_branch = piece_table.current_node();
+        piece_table.undo();
+        piece_table.insert(3, "e");
+        let second_branch = piece_table.current_node();
+        assert!(!piece_table.can_redo());
+        assert_eq!(vec![first_branch, second_branch], piece_table.history_branches());
+
+        assert!(piece_table.jump_to(first_branch));
+        assert_eq!("abcd", piece_table.text());
+        piece_table.undo();
+        assert!(piece_table.redo());
+        assert_eq!("abcd", piece_table.text());
+
+        assert!(piece_table.jump_to(second_branch));
+        assert_eq!("abce", piece_table.text());
+        assert_eq!(3, piece_table.history_nodes().len());
+    }
+
+    #[test]
+    fn test_coalescing() {
+        let mut piece_table = PieceTable::new(String::new());
+        piece_table.set_coalescing(Some(Coalesce {
+            window: None,
+            word_boundary: true,
+        }));
+
+        for (offset, ch) in "hello world".char_indices() {
+            piece_table.insert(offset, &ch.to_string());
+        }
+        piece_table.insert(0, "x");
+        assert_eq!("xhello world", piece_table.text());
+
+        assert!(piece_table.undo());
+        assert_eq!("hello world", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("hello", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("", piece_table.text());
+        assert!(!piece_table.undo());
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let mut piece_table = PieceTable::new("0".to_string());
+        piece_table.set_history_limit(HistoryLimit {
+            max_entries: Some(3),
+            max_bytes: None,
+        });
+
+        for digit in 1..=5 {
+            piece_table.insert(digit, &digit.to_string());
+        }
+        assert_eq!(3, piece_table.history_len());
+
+        while piece_table.undo() {}
+        assert_eq!("012", piece_table.text());
+
+        let memory = piece_table.history_memory();
+        piece_table.set_history_limit(HistoryLimit {
+            max_entries: None,
+            max_bytes: Some(memory - 1),
+        });
+        assert_eq!(3, piece_table.history_len());
+        piece_table.redo();
+        piece_table.set_history_limit(HistoryLimit {
+            max_entries: None,
+            max_bytes: Some(memory - 1),
+        });
+        assert_eq!(2, piece_table.history_len());
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let mut piece_table = PieceTable::new("draft".to_string());
+
+        piece_table.insert(5, " one");
+        piece_table.checkpoint("last save");
+        piece_table.insert(9, " two");
+        piece_table.delete(0, 6);
+        assert_eq!("one two", piece_table.text());
+
+        assert!(piece_table.revert_to_checkpoint("last save"));
+        assert_eq!("draft one", piece_table.text());
+        assert!(piece_table.redo());
+        assert_eq!("draft one two", piece_table.text());
+
+        assert!(!piece_table.revert_to_checkpoint("unknown"));
+        assert!(piece_table.remove_checkpoint("last save"));
+        assert!(!piece_table.revert_to_checkpoint("last save"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_history() {
+        let mut piece_table = PieceTable::new("abc".to_string());
+        piece_table.insert(3, "def");
+        piece_table.delete(0, 1);
+        piece_table.undo();
+
+        let json = serde_json::to_string(&piece_table).unwrap();
+        let mut restored: PieceTable = serde_json::from_str(&json).unwrap();
+        assert_eq!("abcdef", restored.text());
+
+        assert!(restored.redo());
+        assert_eq!("bcdef", restored.text());
+        assert!(restored.undo());
+        assert!(restored.undo());
+        assert_eq!("abc", restored.text());
+    }
+
+    #[test]
+    fn test_delete_ret() {
+        let mut piece_table = PieceTable::new("hello world".to_string());
+        piece_table.insert(5, ",");
+        assert_eq!(", wor", piece_table.delete_ret(5, 5));
+        assert_eq!("ld", piece_table.delete_ret(5, 100));
+        assert_eq!("", piece_table.delete_ret(10, 1));
+        assert_eq!("hello", piece_table.text());
+    }
+
+    #[test]
+    fn test_push_str() {
+        let mut piece_table = PieceTable::new("log:".to_string());
+        for line in ["\nstarted", "\nrunning", "\nstopped"] {
+            piece_table.push_str(line);
+        }
+        assert_eq!("log:\nstarted\nrunning\nstopped", piece_table.text());
+        assert_eq!(2, piece_table.pieces.len());
+
+        piece_table.insert(4, " x");
+        piece_table.push_str("!");
+        assert_eq!(4, piece_table.pieces.len());
+        assert!(piece_table.undo());
+        assert!(piece_table.undo());
+        assert!(piece_table.undo());
+        assert_eq!("log:\nstarted\nrunning", piece_table.text());
+        assert!(piece_table.redo());
+        assert_eq!("log:\nstarted\nrunning\nstopped", piece_table.text());
+    }
+
+    #[test]
+    fn test_conversions() {
+        let from_str: PieceTable = "abc".parse().unwrap();
+        assert_eq!(PieceTable::from("abc"), from_str);
+        assert_eq!(PieceTable::from("abc".to_string()), from_str);
+        assert!(PieceTable::default().is_empty());
+        assert_eq!(PieceTable::new(String::new()), PieceTable::default());
+    }
+
+    #[test]
+    fn test_concatenation() {
+        let mut greeting = PieceTable::from("hello") + ", ";
+        greeting += "world";
+        let document = greeting + PieceTable::from("!\n");
+        assert_eq!("hello, world!\n", document.text());
+        assert_eq!(3, document.history_len());
+    }
+
+    #[test]
+    fn test_extend_and_collect() {
+        let mut piece_table: PieceTable = "héllo".chars().collect();
+        piece_table.extend([',', ' ']);
+        piece_table.extend(["wor", "ld"]);
+        assert_eq!("héllo, world", piece_table.text());
+        assert_eq!(2, piece_table.history_len());
+
+        let lines: PieceTable = ["a\n", "b\n"].into_iter().map(String::from).collect();
+        assert_eq!("a\nb\n", lines.text());
+        assert!(!lines.can_undo());
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut piece_table = PieceTable::new(String::new());
+        assert_eq!(None, piece_table.pop());
+
+        piece_table.push('a');
+        piece_table.push('é');
+        piece_table.insert_char(1, '→');
+        assert_eq!("a→é", piece_table.text());
+
+        assert_eq!(Some('é'), piece_table.pop());
+        assert_eq!(Some('→'), piece_table.pop());
+        assert_eq!("a", piece_table.text());
+        assert_eq!(Some('a'), piece_table.pop());
+        assert_eq!(None, piece_table.pop());
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut piece_table = PieceTable::new("hello world".to_string());
+        piece_table.insert(5, ",");
+        piece_table.truncate(100);
+        assert_eq!("hello, world", piece_table.text());
+
+        piece_table.truncate(8);
+        assert_eq!("hello, w", piece_table.text());
+        piece_table.truncate(3);
+        assert_eq!("hel", piece_table.text());
+        assert_eq!(1, piece_table.pieces.len());
+
+        assert!(piece_table.undo());
+        assert_eq!("hello, w", piece_table.text());
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut piece_table = PieceTable::new("hello".to_string());
+        piece_table.insert(5, " world");
+        assert!(!piece_table.is_empty());
+
+        piece_table.clear();
+        assert!(piece_table.is_empty());
+        assert_eq!("", piece_table.text());
+        assert_eq!(" world", piece_table.add.as_str());
+
+        assert!(piece_table.undo());
+        assert_eq!("hello world", piece_table.text());
+        assert!(PieceTable::new(String::new()).is_empty());
+    }
+
+    #[test]
+    fn test_piece_at() {
+        let mut piece_table = PieceTable::default();
+        for ch in "fragmented".chars().rev() {
+            piece_table.insert_char(0, ch);
+        }
+        piece_table.delete(3, 2);
+        piece_table.undo();
+        assert_eq!("fragmented", piece_table.text());
+
+        let mut start = 0;
+        for (index, piece) in piece_table.pieces.iter().enumerate() {
+            assert_eq!(start, piece_table.piece_starts[index]);
+            if piece.length > 0 {
+                assert_eq!(Some((*piece, index, start)), piece_table.piece_at(start + piece.length - 1));
+            }
+            start += piece.length;
+        }
+        assert_eq!(piece_table.pieces.len(), piece_table.piece_starts.len());
+        assert_eq!(piece_table.pieces.len(), piece_table.piece_lines.len());
+        assert_eq!(None, piece_table.piece_at(10));
+        assert_eq!(None, PieceTable::default().piece_at(0));
+    }
+
+    #[test]
+    fn test_cached_length() {
+        let mut piece_table = PieceTable::new("hello".to_string());
+        let pieces_length = |table: &PieceTable| table.pieces.iter().map(|piece| piece.length).sum::<usize>();
+        piece_table.insert(5, " world");
+        piece_table.push_str("!");
+        piece_table.delete(0, 1);
+        piece_table.replace(0..4, "J");
+        assert_eq!("J world!", piece_table.text());
+        assert_eq!(8, piece_table.len());
+        assert_eq!(pieces_length(&piece_table), piece_table.length());
+
+        assert!(piece_table.undo());
+        assert_eq!(11, piece_table.len());
+        let tail = piece_table.split_off(5);
+        assert_eq!((5, 6), (piece_table.len(), tail.len()));
+        piece_table.append_table(tail);
+        assert_eq!(11, piece_table.fork().len());
+        assert_eq!(pieces_length(&piece_table), piece_table.length());
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut piece_table = PieceTable::new("hello world".to_string());
+        piece_table.start_recording();
+        piece_table.replace(0..5, "howdy");
+        piece_table.replace(6..100, "there");
+        assert_eq!("howdy there", piece_table.text());
+        assert_eq!(2, piece_table.history_len());
+        assert_eq!(4, piece_table.stop_recording().len());
+
+        assert!(piece_table.undo());
+        assert_eq!("howdy world", piece_table.text());
+    }
+
+    #[test]
+    fn test_splice() {
+        let mut piece_table = PieceTable::new("one two three".to_string());
+        piece_table.insert(3, " and");
+        assert_eq!(" and two", piece_table.splice(3..11, ","));
+        assert_eq!("one, three", piece_table.text());
+        assert_eq!("", piece_table.splice(10..10, "!"));
+        assert_eq!("one, three!", piece_table.text());
+
+        assert!(piece_table.undo());
+        assert!(piece_table.undo());
+        assert_eq!("one and two three", piece_table.text());
+    }
+
+    #[test]
+    fn test_selective_undo() {
+        let mut piece_table = PieceTable::new("one two three".to_string());
+
+        piece_table.insert(3, " and a half");
+        let half = piece_table.current_node();
+        piece_table.insert(0, "[");
+        piece_table.delete(19, 6);
+        piece_table.insert(19, "]");
+        assert_eq!("[one and a half two]", piece_table.text());
+
+        assert!(piece_table.selective_undo(half));
+        assert_eq!("[one two]", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("[one and a half two]", piece_table.text());
+
+        piece_table.insert(4, "!");
+        let bang = piece_table.current_node();
+        piece_table.delete(3, 3);
+        assert!(!piece_table.selective_undo(bang));
+        assert_eq!("[onand a half two]", piece_table.text());
+    }
 }
@@ -0,0 +1,148 @@
+use crate::PieceTable;
+use std::borrow::Cow;
+use std::ops::Range;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    Cr,
+    Mixed,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> Option<&'static str> {
+        match self {
+            LineEnding::Lf => Some("\n"),
+            LineEnding::Crlf => Some("\r\n"),
+            LineEnding::Cr => Some("\r"),
+            LineEnding::Mixed => None,
+        }
+    }
+}
+
+impl PieceTable {
+    /// Returns the terminator used by every line, `Mixed` if they disagree, or `Lf` when the
+    /// content has no line terminators at all.
+    pub fn detect_line_ending(&self) -> LineEnding {
+        let mut endings = self.terminators().map(|(_, ending)| ending);
+        let Some(first) = endings.next() else {
+            return LineEnding::default();
+        };
+        if endings.all(|ending| ending == first) {
+            first
+        } else {
+            LineEnding::Mixed
+        }
+    }
+
+    /// Rewrites every terminator that differs from `target` as a single undoable edit. The text
+    /// between terminators keeps its pieces, and all rewritten terminators share one add-buffer
+    /// entry. `Mixed` is not a convertible target and leaves the table unchanged.
+    pub fn convert_line_endings(&mut self, target: LineEnding) {
+        let Some(terminator) = target.as_str() else {
+            return;
+        };
+        let stale: Vec<Range<usize>> = self
+            .terminators()
+            .filter(|&(_, ending)| ending != target)
+            .map(|(offset, ending)| offset..offset + ending.as_str().map_or(0, str::len))
+            .collect();
+        if stale.is_empty() {
+            return;
+        }
+
+        let terminator_piece = self.push_add(terminator);
+        let mut converted = Vec::new();
+        let mut kept = 0;
+        for range in stale {
+            converted.extend(self.pieces_in(kept..range.start));
+            converted.push(terminator_piece);
+            kept = range.end;
+        }
+        let length = self.length();
+        converted.extend(self.pieces_in(kept..length));
+        self.edit(0, length, converted);
+    }
+
+    pub fn insert_line_ending(&self) -> Option<LineEnding> {
+        self.insert_line_ending
+    }
+
+    /// When set, line terminators in inserted text are rewritten to the given ending before they
+    /// reach the table. `None` and `Mixed` insert text as given.
+    pub fn set_insert_line_ending(&mut self, line_ending: Option<LineEnding>) {
+        self.insert_line_ending = line_ending;
+    }
+
+    pub(crate) fn normalize_insert<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        match self.insert_line_ending.and_then(LineEnding::as_str) {
+            Some(terminator) if content.contains(['\r', '\n']) => {
+                let mut normalized = String::with_capacity(content.len());
+                let mut rest = content;
+                while let Some(index) = rest.find(['\r', '\n']) {
+                    normalized.push_str(&rest[..index]);
+                    normalized.push_str(terminator);
+                    let length = if rest[index..].starts_with("\r\n") { 2 } else { 1 };
+                    rest = &rest[index + length..];
+                }
+                normalized.push_str(rest);
+                Cow::Owned(normalized)
+            }
+            _ => Cow::Borrowed(content),
+        }
+    }
+
+    fn terminators(&self) -> impl Iterator<Item = (usize, LineEnding)> + '_ {
+        let mut bytes = self.bytes().enumerate().peekable();
+        std::iter::from_fn(move || loop {
+            let (offset, byte) = bytes.next()?;
+            match byte {
+                b'\n' => return Some((offset, LineEnding::Lf)),
+                b'\r' if bytes.next_if(|&(_, byte)| byte == b'\n').is_some() => return Some((offset, LineEnding::Crlf)),
+                b'\r' => return Some((offset, LineEnding::Cr)),
+                _ => {}
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_line_ending() {
+        assert_eq!(LineEnding::Lf, PieceTable::from("no terminators").detect_line_ending());
+        assert_eq!(LineEnding::Lf, PieceTable::from("a\nb\n").detect_line_ending());
+        assert_eq!(LineEnding::Cr, PieceTable::from("a\rb\r").detect_line_ending());
+        assert_eq!(LineEnding::Mixed, PieceTable::from("a\r\nb\n").detect_line_ending());
+
+        let mut piece_table = PieceTable::from("a\r");
+        piece_table.push_str("\nb\r");
+        piece_table.insert(5, "\n");
+        assert_eq!(LineEnding::Crlf, piece_table.detect_line_ending());
+    }
+
+    #[test]
+    fn test_convert_line_endings() {
+        let mut piece_table = PieceTable::from("one\r\ntwo\nthree\r");
+        piece_table.insert(5, "\nfour");
+        piece_table.convert_line_endings(LineEnding::Lf);
+        assert_eq!("one\n\nfourtwo\nthree\n", piece_table.text());
+        assert_eq!(LineEnding::Lf, piece_table.detect_line_ending());
+        assert_eq!(piece_table.text().matches('\n').count() + 1, piece_table.line_count());
+
+        piece_table.convert_line_endings(LineEnding::Lf);
+        piece_table.convert_line_endings(LineEnding::Mixed);
+        assert_eq!(2, piece_table.history_len());
+        assert!(piece_table.undo());
+        assert_eq!("one\r\n\nfourtwo\nthree\r", piece_table.text());
+
+        piece_table.set_insert_line_ending(Some(LineEnding::Crlf));
+        piece_table.push_str("\nend\r\n\r");
+        assert_eq!("one\r\n\nfourtwo\nthree\r\r\nend\r\n\r\n", piece_table.text());
+    }
+}
@@ -0,0 +1,358 @@
+use crate::{Buffer, Piece, PieceTable};
+use std::borrow::Cow;
+use std::ops::{Bound, Range, RangeBounds};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct LineBreaks {
+    original: Arc<[usize]>,
+    add: Arc<Vec<usize>>,
+}
+
+impl LineBreaks {
+    pub(crate) fn new(original: &str) -> Self {
+        LineBreaks {
+            original: break_positions(original, 0).collect(),
+            add: Arc::default(),
+        }
+    }
+
+    pub(crate) fn push_add(&mut self, offset: usize, content: &str) {
+        Arc::make_mut(&mut self.add).extend(break_positions(content, offset));
+    }
+
+    pub(crate) fn piece(&self, buffer: Buffer, length: usize, offset: usize) -> Piece {
+        Piece::new(buffer, length, offset, self.count(buffer, offset, length))
+    }
+
+    pub(crate) fn count(&self, buffer: Buffer, offset: usize, length: usize) -> usize {
+        let breaks = self.buffer(buffer);
+        breaks.partition_point(|&position| position < offset + length) - breaks.partition_point(|&position| position < offset)
+    }
+
+    fn nth(&self, buffer: Buffer, offset: usize, n: usize) -> usize {
+        let breaks = self.buffer(buffer);
+        breaks[breaks.partition_point(|&position| position < offset) + n]
+    }
+
+    fn buffer(&self, buffer: Buffer) -> &[usize] {
+        match buffer {
+            Buffer::Original => &self.original,
+            Buffer::Add => &self.add,
+        }
+    }
+}
+
+#[cfg(feature = "memchr")]
+fn break_positions(text: &str, offset: usize) -> impl Iterator<Item = usize> + '_ {
+    memchr::memchr_iter(b'\n', text.as_bytes()).map(move |index| offset + index)
+}
+
+#[cfg(not(feature = "memchr"))]
+fn break_positions(text: &str, offset: usize) -> impl Iterator<Item = usize> + '_ {
+    text.match_indices('\n').map(move |(index, _)| offset + index)
+}
+
+impl PieceTable {
+    pub fn line_count(&self) -> usize {
+        match (self.piece_lines.last(), self.pieces.last()) {
+            (Some(&line), Some(piece)) => line + piece.line_breaks + 1,
+            _ => 1,
+        }
+    }
+
+    /// Counts the lines `range` touches, that is the line breaks inside it plus one.
+    pub fn count_lines_in(&self, range: impl RangeBounds<usize>) -> usize {
+        let range = self.resolve_range(range);
+        self.byte_to_line(range.end) - self.byte_to_line(range.start) + 1
+    }
+
+    pub fn line(&self, line: usize) -> Option<Cow<'_, str>> {
+        if line >= self.line_count() {
+            return None;
+        }
+        Some(self.slice_cow(self.line_range(line)))
+    }
+
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let Some(start) = self.line_start(line) else {
+            return self.length()..self.length();
+        };
+        let end = match self.line_start(line + 1) {
+            Some(next) if self.slice(next.saturating_sub(2)..next) == "\r\n" => next - 2,
+            Some(next) => next - 1,
+            None => self.length(),
+        };
+        start..end
+    }
+
+    pub fn line_to_byte(&self, line: usize) -> usize {
+        self.line_start(line).unwrap_or(self.length())
+    }
+
+    pub fn byte_to_line(&self, offset: usize) -> usize {
+        match self.piece_at(offset) {
+            Some((piece, index, running_total)) => {
+                self.piece_lines[index] + self.line_breaks.count(piece.buffer, piece.offset, offset - running_total)
+            }
+            None => self.line_count() - 1,
+        }
+    }
+
+    pub fn position_of(&self, offset: usize) -> (usize, usize) {
+        let line = self.byte_to_line(offset);
+        let column = self.count_chars_in(self.line_to_byte(line)..self.floor_char_boundary(offset));
+        (line, column)
+    }
+
+    pub fn offset_of(&self, line: usize, column: usize) -> usize {
+        let range = self.line_range(line);
+        let mut remaining = column;
+        let mut running_total = range.start;
+        for chunk in self.chunks_in_range(range.clone()) {
+            if let Some((index, _)) = chunk.char_indices().nth(remaining) {
+                return running_total + index;
+            }
+            remaining -= chunk.chars().count();
+            running_total += chunk.len();
+        }
+        range.end
+    }
+
+    pub fn insert_at(&mut self, line: usize, column: usize, content: &str) {
+        let offset = self.offset_of(line, column);
+        self.insert(offset, content);
+    }
+
+    pub fn delete_lines(&mut self, lines: impl RangeBounds<usize>) {
+        let Range { start: first, end } = self.resolve_lines(lines);
+        if first >= end {
+            return;
+        }
+
+        let start = if end == self.line_count() && first > 0 {
+            self.line_range(first - 1).end
+        } else {
+            self.line_to_byte(first)
+        };
+        let end = self.line_to_byte(end);
+        self.delete(start, end - start);
+    }
+
+    /// Joins the lines in `lines` into one, replacing each line break with `separator`. With
+    /// `collapse_indent`, the leading spaces and tabs of every joined line are dropped as well.
+    pub fn join_lines(&mut self, lines: impl RangeBounds<usize>, separator: &str, collapse_indent: bool) {
+        let Range { start: first, end } = self.resolve_lines(lines);
+        if end <= first + 1 {
+            return;
+        }
+
+        self.transaction(|table| {
+            for line in (first..end - 1).rev() {
+                let start = table.line_range(line).end;
+                let mut next = table.line_to_byte(line + 1);
+                if collapse_indent {
+                    let content = table.slice_cow(table.line_range(line + 1));
+                    next += content.len() - content.trim_start_matches([' ', '\t']).len();
+                }
+                table.replace(start..next, separator);
+            }
+        });
+    }
+
+    /// Inserts a copy of line `line` below it. A last line without a terminator gets the one used
+    /// by the line above, or `\n` if there is none.
+    pub fn duplicate_line(&mut self, line: usize) {
+        if line >= self.line_count() {
+            return;
+        }
+
+        let range = self.line_range(line);
+        match self.line_start(line + 1) {
+            Some(next) => {
+                self.copy_range(range.start..next, next);
+            }
+            None => {
+                let terminator = match line.checked_sub(1) {
+                    Some(previous) => self.slice(self.line_range(previous).end..range.start),
+                    None => "\n".to_string(),
+                };
+                self.transaction(|table| {
+                    table.insert(range.end, &terminator);
+                    table.copy_range(range.clone(), range.end + terminator.len());
+                });
+            }
+        }
+    }
+
+    pub(crate) fn resolve_lines(&self, lines: impl RangeBounds<usize>) -> Range<usize> {
+        let line_count = self.line_count();
+        let first = match lines.start_bound() {
+            Bound::Included(&first) => first,
+            Bound::Excluded(&first) => first.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match lines.end_bound() {
+            Bound::Included(&last) => last.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => line_count,
+        };
+        first..end.min(line_count)
+    }
+
+    fn line_start(&self, line: usize) -> Option<usize> {
+        if line == 0 {
+            return Some(0);
+        }
+
+        if line >= self.line_count() {
+            return None;
+        }
+        // The last piece that starts before the line holds the break that ends the line before it.
+        let index = self.piece_lines.partition_point(|&start| start < line) - 1;
+        let piece = self.pieces[index];
+        let position = self.line_breaks.nth(piece.buffer, piece.offset, line - self.piece_lines[index] - 1);
+        Some(self.piece_starts[index] + position - piece.offset + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_count() {
+        let mut piece_table = PieceTable::from("one\ntwo");
+        assert_eq!(2, piece_table.line_count());
+
+        piece_table.insert(3, "\none and a half\r\n");
+        piece_table.push('\n');
+        assert_eq!(5, piece_table.line_count());
+        assert_eq!(1, PieceTable::default().line_count());
+    }
+
+    #[test]
+    fn test_line_breaks_follow_edits() {
+        let mut piece_table = PieceTable::from("a\nb\nc\nd");
+        piece_table.insert(3, "x\ny\n");
+        piece_table.delete(1, 3);
+        piece_table.copy_range(0..4, 9);
+        piece_table.retain(|ch| ch != 'y');
+        piece_table.undo();
+        piece_table.move_range(2..5, 0);
+
+        for piece in &piece_table.pieces {
+            assert_eq!(piece_table.piece_text(piece).matches('\n').count(), piece.line_breaks);
+        }
+        assert_eq!(piece_table.text().matches('\n').count() + 1, piece_table.line_count());
+        assert_eq!(piece_table.to_string().find('\n').map(|index| index + 1), piece_table.line_start(1));
+    }
+
+    #[test]
+    fn test_count_lines_in() {
+        let mut piece_table = PieceTable::from("one\ntwo\nthree\n");
+        piece_table.insert(4, "1.5\n");
+        assert_eq!("one\n1.5\ntwo\nthree\n", piece_table.text());
+        assert_eq!(5, piece_table.count_lines_in(..));
+        assert_eq!(3, piece_table.count_lines_in(2..10));
+        assert_eq!(2, piece_table.count_lines_in(4..8));
+        assert_eq!(1, piece_table.count_lines_in(5..5));
+    }
+
+    #[test]
+    fn test_line() {
+        let mut piece_table = PieceTable::from("one\ntwo\r\nthree");
+        piece_table.insert(6, "\n");
+        assert_eq!("one\ntw\no\r\nthree", piece_table.text());
+
+        assert_eq!(Some(Cow::Borrowed("one")), piece_table.line(0));
+        assert_eq!(Some("tw".into()), piece_table.line(1));
+        assert_eq!(7..8, piece_table.line_range(2));
+        assert_eq!(Some("three".into()), piece_table.line(3));
+        assert_eq!(None, piece_table.line(4));
+        assert_eq!(15..15, piece_table.line_range(4));
+
+        piece_table.push('\n');
+        assert_eq!(Some("".into()), piece_table.line(4));
+    }
+
+    #[test]
+    fn test_line_to_byte() {
+        let mut piece_table = PieceTable::from("a\nbb\nccc");
+        piece_table.insert(2, "x\n");
+        assert_eq!("a\nx\nbb\nccc", piece_table.text());
+
+        assert_eq!(
+            vec![0, 2, 4, 7, 10],
+            (0..5).map(|line| piece_table.line_to_byte(line)).collect::<Vec<_>>()
+        );
+        assert_eq!(0, piece_table.byte_to_line(1));
+        assert_eq!(1, piece_table.byte_to_line(2));
+        assert_eq!(2, piece_table.byte_to_line(6));
+        assert_eq!(3, piece_table.byte_to_line(100));
+    }
+
+    #[test]
+    fn test_position_of() {
+        let mut piece_table = PieceTable::from("héllo\r\nwörld");
+        piece_table.insert(11, "!");
+        assert_eq!((0, 0), piece_table.position_of(0));
+        assert_eq!((0, 1), piece_table.position_of(2));
+        assert_eq!((0, 2), piece_table.position_of(3));
+        assert_eq!((1, 2), piece_table.position_of(11));
+        assert_eq!((1, 6), piece_table.position_of(15));
+
+        assert_eq!(3, piece_table.offset_of(0, 2));
+        assert_eq!(6, piece_table.offset_of(0, 100));
+        assert_eq!(11, piece_table.offset_of(1, 2));
+        assert_eq!(12, piece_table.offset_of(1, 3));
+        assert_eq!(15, piece_table.offset_of(5, 0));
+    }
+
+    #[test]
+    fn test_line_editing() {
+        let mut piece_table = PieceTable::from("fn main() {\n}\n");
+        piece_table.insert_at(1, 0, "    println!();\n");
+        piece_table.insert_at(0, 100, " // entry");
+        assert_eq!("fn main() { // entry\n    println!();\n}\n", piece_table.text());
+
+        piece_table.delete_lines(1..2);
+        assert_eq!("fn main() { // entry\n}\n", piece_table.text());
+        piece_table.delete_lines(1..);
+        assert_eq!("fn main() { // entry", piece_table.text());
+        piece_table.delete_lines(..=0);
+        assert!(piece_table.is_empty());
+    }
+
+    #[test]
+    fn test_join_lines() {
+        let mut piece_table = PieceTable::from("fn main() {\n    let x = 1;\r\n\tx\n}\n");
+        piece_table.join_lines(0..3, " ", true);
+        assert_eq!("fn main() { let x = 1; x\n}\n", piece_table.text());
+        assert_eq!(3, piece_table.line_count());
+        assert!(piece_table.undo());
+
+        piece_table.join_lines(2.., "", false);
+        assert_eq!("fn main() {\n    let x = 1;\r\n\tx}", piece_table.text());
+        piece_table.join_lines(1..2, ", ", false);
+        assert_eq!(1, piece_table.history_len());
+    }
+
+    #[test]
+    fn test_duplicate_line() {
+        let mut piece_table = PieceTable::from("one\r\ntwo");
+        piece_table.duplicate_line(0);
+        assert_eq!("one\r\none\r\ntwo", piece_table.text());
+        piece_table.duplicate_line(2);
+        assert_eq!("one\r\none\r\ntwo\r\ntwo", piece_table.text());
+        assert_eq!("\r\n", piece_table.add.as_str());
+
+        assert!(piece_table.undo());
+        piece_table.duplicate_line(9);
+        assert_eq!(2, piece_table.history_len());
+        let mut single = PieceTable::from("only");
+        single.duplicate_line(0);
+        assert_eq!("only\nonly", single.text());
+    }
+}
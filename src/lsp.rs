@@ -0,0 +1,150 @@
+use crate::PieceTable;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LspPosition {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    fn units(self, ch: char) -> usize {
+        match self {
+            PositionEncoding::Utf8 => ch.len_utf8(),
+            PositionEncoding::Utf16 => ch.len_utf16(),
+            PositionEncoding::Utf32 => 1,
+        }
+    }
+}
+
+impl PieceTable {
+    pub fn apply_lsp_change(&mut self, range: Option<LspRange>, text: &str) {
+        self.apply_lsp_change_with(PositionEncoding::default(), range, text);
+    }
+
+    pub fn apply_lsp_change_with(&mut self, encoding: PositionEncoding, range: Option<LspRange>, text: &str) {
+        let (start, end) = match range {
+            Some(range) => {
+                let start = self.lsp_offset(range.start, encoding);
+                let end = self.lsp_offset(range.end, encoding);
+                (start.min(end), start.max(end))
+            }
+            None => (0, self.length()),
+        };
+
+        self.replace(start..end, text);
+    }
+
+    /// Converts `position` to a byte offset. A character past the end of its line clamps to the
+    /// end of the line's content, before any `\r\n`, and a line past the end to the end of the text.
+    pub fn lsp_offset(&self, position: LspPosition, encoding: PositionEncoding) -> usize {
+        let line = position.line as usize;
+        if line >= self.line_count() {
+            return self.length();
+        }
+
+        let range = self.line_range(line);
+        let mut units = 0;
+        let mut offset = range.start;
+        for ch in self.chunks_in_range(range.clone()).flat_map(str::chars) {
+            if units >= position.character as usize {
+                return offset;
+            }
+            units += encoding.units(ch);
+            offset += ch.len_utf8();
+        }
+        range.end
+    }
+
+    pub fn lsp_position(&self, offset: usize, encoding: PositionEncoding) -> LspPosition {
+        let line = self.byte_to_line(offset);
+        let character = self
+            .chunks_in_range(self.line_to_byte(line)..offset)
+            .flat_map(str::chars)
+            .map(|ch| encoding.units(ch))
+            .sum::<usize>();
+        LspPosition {
+            line: line as u32,
+            character: character as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Option<LspRange> {
+        Some(LspRange {
+            start: LspPosition {
+                line: start.0,
+                character: start.1,
+            },
+            end: LspPosition {
+                line: end.0,
+                character: end.1,
+            },
+        })
+    }
+
+    #[test]
+    fn test_apply_lsp_change() {
+        let mut piece_table = PieceTable::new("let 𝄞 = 1;\nlet b = 2;\n".to_string());
+
+        piece_table.apply_lsp_change(range((0, 9), (0, 10)), "42");
+        assert_eq!("let 𝄞 = 42;\nlet b = 2;\n", piece_table.text());
+        piece_table.apply_lsp_change(range((1, 4), (1, 99)), "c");
+        assert_eq!("let 𝄞 = 42;\nlet c\n", piece_table.text());
+        piece_table.apply_lsp_change(range((5, 0), (5, 0)), "// end");
+        assert_eq!("let 𝄞 = 42;\nlet c\n// end", piece_table.text());
+
+        assert_eq!(
+            LspPosition { line: 0, character: 6 },
+            piece_table.lsp_position(8, PositionEncoding::Utf16)
+        );
+        assert_eq!(
+            LspPosition { line: 0, character: 5 },
+            piece_table.lsp_position(8, PositionEncoding::Utf32)
+        );
+
+        piece_table.apply_lsp_change(None, "replaced");
+        assert_eq!("replaced", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("let 𝄞 = 42;\nlet c\n// end", piece_table.text());
+    }
+
+    #[test]
+    fn test_lsp_crlf() {
+        let mut piece_table = PieceTable::from("ab\r\ncd\r\n");
+        piece_table.insert(1, "é");
+        assert_eq!(4, piece_table.lsp_offset(LspPosition { line: 0, character: 9 }, PositionEncoding::Utf16));
+        assert_eq!(4, piece_table.lsp_offset(LspPosition { line: 0, character: 4 }, PositionEncoding::Utf8));
+        assert_eq!(
+            LspPosition { line: 1, character: 1 },
+            piece_table.lsp_position(7, PositionEncoding::Utf16)
+        );
+
+        piece_table.apply_lsp_change(range((0, 3), (1, 0)), " ");
+        assert_eq!("aéb cd\r\n", piece_table.text());
+        piece_table.apply_lsp_change(range((0, 99), (0, 99)), "!");
+        assert_eq!("aéb cd!\r\n", piece_table.text());
+        assert!(piece_table.undo());
+        assert!(piece_table.undo());
+        assert_eq!("aéb\r\ncd\r\n", piece_table.text());
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{Edit, Overlap, PieceTable, Snapshot};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub ancestor: Range<usize>,
+    pub ours: String,
+    pub theirs: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EditConflict {
+    pub ours: usize,
+    pub theirs: usize,
+    pub overlap: Overlap,
+}
+
+pub fn detect_conflicts(ours: &[Edit], theirs: &[Edit]) -> Vec<EditConflict> {
+    let mut conflicts = Vec::new();
+    for (our_index, our) in ours.iter().enumerate() {
+        for (their_index, their) in theirs.iter().enumerate() {
+            let overlap = our.overlap(their);
+            if overlap != Overlap::Disjoint {
+                conflicts.push(EditConflict {
+                    ours: our_index,
+                    theirs: their_index,
+                    overlap,
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Hunk {
+    pub(crate) range: Range<usize>,
+    pub(crate) text: String,
+}
+
+impl Hunk {
+    pub(crate) fn conflicts_with(&self, other: &Hunk) -> bool {
+        if self == other {
+            return false;
+        }
+        let both_inserts_at_same_offset = self.range.is_empty() && other.range.is_empty() && self.range.start == other.range.start;
+        both_inserts_at_same_offset || (self.range.start < other.range.end && other.range.start < self.range.end)
+    }
+}
+
+pub(crate) fn hunks(edits: &[Edit]) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut shift: isize = 0;
+    let mut last_end = None;
+
+    for edit in edits {
+        match edit {
+            Edit::Delete { range } => {
+                let start = range.start.checked_add_signed(-shift).expect("edit script is ordered");
+                hunks.push(Hunk {
+                    range: start..start + range.len(),
+                    text: String::new(),
+                });
+                shift -= range.len() as isize;
+                last_end = Some(range.start);
+            }
+            Edit::Replace { range, text } => {
+                let start = range.start.checked_add_signed(-shift).expect("edit script is ordered");
+                hunks.push(Hunk {
+                    range: start..start + range.len(),
+                    text: text.clone(),
+                });
+                shift += text.len() as isize - range.len() as isize;
+                last_end = Some(range.start + text.len());
+            }
+            Edit::Insert { offset, text } => {
+                let start = offset.checked_add_signed(-shift).expect("edit script is ordered");
+                match hunks.last_mut() {
+                    Some(hunk) if last_end == Some(*offset) => hunk.text.push_str(text),
+                    _ => hunks.push(Hunk {
+                        range: start..start,
+                        text: text.clone(),
+                    }),
+                }
+                shift += text.len() as isize;
+                last_end = Some(offset + text.len());
+            }
+        }
+    }
+    hunks
+}
+
+impl PieceTable {
+    pub fn merge(&mut self, ancestor: &Snapshot, theirs: &PieceTable) -> Vec<Conflict> {
+        let ours = hunks(&ancestor.diff(&self.snapshot()));
+        let theirs = hunks(&ancestor.diff(&theirs.snapshot()));
+
+        let mut conflicts = Vec::new();
+        let mut accepted = Vec::new();
+        for hunk in theirs {
+            if ours.contains(&hunk) {
+                continue;
+            }
+            match ours.iter().find(|our| our.conflicts_with(&hunk)) {
+                Some(our) => conflicts.push(Conflict {
+                    ancestor: hunk.range.clone(),
+                    ours: our.text.clone(),
+                    theirs: hunk.text,
+                }),
+                None => {
+                    let shift: isize = ours
+                        .iter()
+                        .filter(|our| our.range.end <= hunk.range.start)
+                        .map(|our| our.text.len() as isize - our.range.len() as isize)
+                        .sum();
+                    let start = hunk.range.start.checked_add_signed(shift).expect("shifted hunk stays in bounds");
+                    accepted.push((start, hunk.range.len(), hunk.text));
+                }
+            }
+        }
+
+        self.transaction(|table| {
+            for (start, length, text) in accepted.into_iter().rev() {
+                table.replace(start..start + length, &text);
+            }
+        });
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_conflicts() {
+        let insert = |offset: usize| Edit::Insert {
+            offset,
+            text: "x".to_string(),
+        };
+        let ours = vec![Edit::Delete { range: 0..4 }, insert(10), insert(20)];
+        let theirs = vec![insert(4), Edit::Delete { range: 8..12 }, insert(20), insert(30)];
+
+        assert_eq!(
+            vec![
+                EditConflict {
+                    ours: 0,
+                    theirs: 0,
+                    overlap: Overlap::Adjacent,
+                },
+                EditConflict {
+                    ours: 1,
+                    theirs: 1,
+                    overlap: Overlap::Overlapping,
+                },
+                EditConflict {
+                    ours: 2,
+                    theirs: 2,
+                    overlap: Overlap::Overlapping,
+                },
+            ],
+            detect_conflicts(&ours, &theirs)
+        );
+        assert_eq!(Overlap::Disjoint, insert(0).overlap(&insert(1)));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut ours = PieceTable::new("alpha beta gamma delta".to_string());
+        let ancestor = ours.snapshot();
+        let mut theirs = ours.fork();
+
+        ours.delete(0, 5);
+        ours.insert(0, "ALPHA");
+        ours.insert(ours.length(), "!");
+        theirs.delete(11, 5);
+        theirs.insert(11, "GAMMA");
+        theirs.insert(22, "?");
+
+        let conflicts = ours.merge(&ancestor, &theirs);
+        assert_eq!("ALPHA beta GAMMA delta!", ours.text());
+        assert_eq!(
+            vec![Conflict {
+                ancestor: 22..22,
+                ours: "!".to_string(),
+                theirs: "?".to_string(),
+            }],
+            conflicts
+        );
+
+        assert!(ours.undo());
+        assert_eq!("ALPHA beta gamma delta!", ours.text());
+    }
+
+    #[test]
+    fn test_merge_non_ascii() {
+        let mut ours = PieceTable::from("crème brûlée");
+        let ancestor = ours.snapshot();
+        let mut theirs = ours.clone();
+
+        ours.push_str(" à la carte");
+        theirs.replace(0..6, "¡crêpe");
+        theirs.push_str("?");
+
+        let conflicts = ours.merge(&ancestor, &theirs);
+        assert_eq!("¡crêpe brûlée à la carte", ours.text());
+        assert_eq!(1, conflicts.len());
+        assert_eq!(" à la carte", conflicts[0].ours);
+        assert_eq!("?", conflicts[0].theirs);
+        assert!(ours.undo());
+        assert_eq!("crème brûlée à la carte", ours.text());
+    }
+}
@@ -0,0 +1,47 @@
+use crate::PieceTable;
+use aho_corasick::automaton::Automaton;
+use aho_corasick::nfa::contiguous::NFA;
+use aho_corasick::{Anchored, BuildError};
+use std::ops::Range;
+
+impl PieceTable {
+    /// Finds non-overlapping occurrences of any of `patterns` in a single pass over the pieces,
+    /// returning the index of the pattern that matched with its range. Matches are reported as
+    /// soon as they end, the same as `aho_corasick::MatchKind::Standard`.
+    pub fn find_any<P: AsRef<[u8]>>(&self, patterns: &[P]) -> Result<Vec<(usize, Range<usize>)>, BuildError> {
+        let automaton = NFA::new(patterns)?;
+        let start = automaton.start_state(Anchored::No).expect("unanchored searches are supported");
+
+        let mut matches = Vec::new();
+        let mut state = start;
+        let mut at = 0;
+        for chunk in self.chunks() {
+            for &byte in chunk.as_bytes() {
+                at += 1;
+                state = automaton.next_state(Anchored::No, state, byte);
+                if automaton.is_match(state) {
+                    let pattern = automaton.match_pattern(state, 0);
+                    matches.push((pattern.as_usize(), at - automaton.pattern_len(pattern)..at));
+                    state = start;
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_any() {
+        let mut piece_table = PieceTable::from("fn main() { let x = true; }");
+        piece_table.insert(20, "!");
+
+        let matches = piece_table.find_any(&["fn", "let", "true", "false"]).unwrap();
+        assert_eq!(vec![(0, 0..2), (1, 12..15), (2, 21..25)], matches);
+        assert_eq!(vec![(0, 0..2), (0, 2..4)], PieceTable::from("aaaab").find_any(&["aa", "ab"]).unwrap());
+        assert!(piece_table.find_any(&["missing"]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::PieceTable;
+use std::ops::{Range, RangeBounds};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Class {
+    Word,
+    Space,
+    Punctuation,
+    Break,
+}
+
+impl Class {
+    fn of(ch: char) -> Self {
+        match ch {
+            '\n' | '\r' => Class::Break,
+            _ if ch.is_alphanumeric() || ch == '_' => Class::Word,
+            _ if ch.is_whitespace() => Class::Space,
+            _ => Class::Punctuation,
+        }
+    }
+}
+
+impl PieceTable {
+    /// Returns the run of word chars, spaces or punctuation around `offset`, like Vim's `iw`. A
+    /// word char is alphanumeric or `_`; at a line break the range is empty.
+    pub fn word_at(&self, offset: usize) -> Range<usize> {
+        let mut start = self.chars_at(offset);
+        let mut end = start.clone();
+        let Some(ch) = end.clone().next().or_else(|| start.clone().prev()) else {
+            return start.offset()..start.offset();
+        };
+        let class = Class::of(ch);
+        if class == Class::Break {
+            return start.offset()..start.offset();
+        }
+
+        loop {
+            let mut probe = start.clone();
+            match probe.prev() {
+                Some(ch) if Class::of(ch) == class => start = probe,
+                _ => break,
+            }
+        }
+        loop {
+            let mut probe = end.clone();
+            match probe.next() {
+                Some(ch) if Class::of(ch) == class => end = probe,
+                _ => break,
+            }
+        }
+        start.offset()..end.offset()
+    }
+
+    /// Returns the line containing `offset`, without its terminator.
+    pub fn line_at(&self, offset: usize) -> Range<usize> {
+        self.line_range(self.byte_to_line(offset))
+    }
+
+    /// Returns the paragraph containing `offset`, or `None` between paragraphs. Only the lines
+    /// out to the blank ones on either side are read.
+    pub fn paragraph_at(&self, offset: usize) -> Option<Range<usize>> {
+        let line = self.byte_to_line(offset);
+        if self.is_blank_line(line) {
+            return None;
+        }
+        let first = (0..line).rev().take_while(|&line| !self.is_blank_line(line)).last().unwrap_or(line);
+        let last = (line + 1..self.line_count())
+            .take_while(|&line| !self.is_blank_line(line))
+            .last()
+            .unwrap_or(line);
+        let paragraph = self.line_to_byte(first)..self.line_range(last).end;
+        Some(paragraph).filter(|paragraph| offset <= paragraph.end)
+    }
+
+    /// Grows `range` to the word objects at both of its ends, see [`PieceTable::word_at`].
+    pub fn expand_to_word(&self, range: impl RangeBounds<usize>) -> Range<usize> {
+        self.expand_with(range, |offset| Some(self.word_at(offset)))
+    }
+
+    /// Grows `range` to whole lines, without the terminator of the last one.
+    pub fn expand_to_line(&self, range: impl RangeBounds<usize>) -> Range<usize> {
+        self.expand_with(range, |offset| Some(self.line_at(offset)))
+    }
+
+    /// Grows `range` to the paragraphs its ends fall in; an end between paragraphs stays put.
+    pub fn expand_to_paragraph(&self, range: impl RangeBounds<usize>) -> Range<usize> {
+        self.expand_with(range, |offset| self.paragraph_at(offset))
+    }
+
+    // The end of a non-empty range is looked up from its last char, so a range that already ends
+    // on a boundary doesn't spill into the next object.
+    fn expand_with(&self, range: impl RangeBounds<usize>, object_at: impl Fn(usize) -> Option<Range<usize>>) -> Range<usize> {
+        let range = self.resolve_range(range);
+        let last = if range.is_empty() {
+            range.end
+        } else {
+            self.prev_char_offset(range.end).unwrap_or(range.start)
+        };
+        let start = object_at(range.start).map_or(range.start, |object| object.start.min(range.start));
+        let end = object_at(last).map_or(range.end, |object| object.end.max(range.end));
+        start..end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_objects() {
+        let mut piece_table = PieceTable::from("let snake_case = a1 + b;\n\nnext  (line)");
+        piece_table.insert(10, "_");
+        assert_eq!("let snake__case = a1 + b;\n\nnext  (line)", piece_table.text());
+
+        assert_eq!(4..15, piece_table.word_at(9));
+        assert_eq!(4..15, piece_table.word_at(4));
+        assert_eq!(3..4, piece_table.word_at(3));
+        assert_eq!(24..25, piece_table.word_at(24));
+        assert_eq!(25..25, piece_table.word_at(25));
+        assert_eq!(31..33, piece_table.word_at(32));
+        assert_eq!(34..38, piece_table.word_at(35));
+        assert_eq!(38..39, piece_table.word_at(39));
+        assert_eq!(0..0, PieceTable::default().word_at(0));
+
+        assert_eq!(0..25, piece_table.line_at(7));
+        assert_eq!(26..26, piece_table.line_at(26));
+        assert_eq!(Some(0..25), piece_table.paragraph_at(25));
+        assert_eq!(None, piece_table.paragraph_at(26));
+        assert_eq!(Some(27..39), piece_table.paragraph_at(30));
+    }
+
+    #[test]
+    fn test_expand_selection() {
+        let mut piece_table = PieceTable::from("one two three\nfive\n\nsix");
+        piece_table.insert(14, "four ");
+        assert_eq!("one two three\nfour five\n\nsix", piece_table.text());
+
+        assert_eq!(4..13, piece_table.expand_to_word(5..10));
+        assert_eq!(4..7, piece_table.expand_to_word(4..7));
+        assert_eq!(4..7, piece_table.expand_to_word(6..6));
+        assert_eq!(0..23, piece_table.expand_to_line(5..16));
+        assert_eq!(14..23, piece_table.expand_to_line(14..14));
+        assert_eq!(0..23, piece_table.expand_to_paragraph(15..15));
+        assert_eq!(24..28, piece_table.expand_to_paragraph(24..26));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::PieceTable;
+use std::fmt::{Debug, Formatter};
+#[cfg(feature = "events")]
+use std::sync::mpsc::{channel, Receiver};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChangeInfo {
+    pub offset: usize,
+    pub deleted: usize,
+    pub inserted: String,
+    pub revision: u64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObserverId(u64);
+
+type Callback = Box<dyn Fn(&ChangeInfo) + Send + Sync>;
+
+#[derive(Default)]
+pub(crate) struct Observers {
+    next_id: u64,
+    callbacks: Vec<(ObserverId, Callback)>,
+}
+
+impl Observers {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    pub(crate) fn notify(&self, info: &ChangeInfo) {
+        for (_, callback) in &self.callbacks {
+            callback(info);
+        }
+    }
+}
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observers").field("count", &self.callbacks.len()).finish()
+    }
+}
+
+// Callbacks belong to whoever registered them on this table; a clone starts without any.
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Observers::default()
+    }
+}
+
+impl PieceTable {
+    pub fn on_change(&mut self, callback: impl Fn(&ChangeInfo) + Send + Sync + 'static) -> ObserverId {
+        let id = ObserverId(self.observers.next_id);
+        self.observers.next_id += 1;
+        self.observers.callbacks.push((id, Box::new(callback)));
+        id
+    }
+
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool {
+        let count = self.observers.callbacks.len();
+        self.observers.callbacks.retain(|(observer, _)| *observer != id);
+        self.observers.callbacks.len() != count
+    }
+
+    #[cfg(feature = "events")]
+    pub fn subscribe(&mut self) -> (ObserverId, Receiver<ChangeInfo>) {
+        let (sender, receiver) = channel();
+        let id = self.on_change(move |info| {
+            let _ = sender.send(info.clone());
+        });
+        (id, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_on_change() {
+        let mut piece_table = PieceTable::new("hello world".to_string());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let id = piece_table.on_change(move |info| sink.lock().unwrap().push(info.clone()));
+
+        piece_table.delete(5, 6);
+        piece_table.insert(5, "!");
+        piece_table.undo();
+        assert_eq!(
+            vec![
+                ChangeInfo {
+                    offset: 5,
+                    deleted: 6,
+                    inserted: String::new(),
+                    revision: 1,
+                },
+                ChangeInfo {
+                    offset: 5,
+                    deleted: 0,
+                    inserted: "!".to_string(),
+                    revision: 2,
+                },
+                ChangeInfo {
+                    offset: 5,
+                    deleted: 1,
+                    inserted: String::new(),
+                    revision: 3,
+                },
+            ],
+            *seen.lock().unwrap()
+        );
+
+        assert!(piece_table.clone().observers.is_empty());
+        assert!(piece_table.remove_observer(id));
+        piece_table.insert(0, "x");
+        assert_eq!(3, seen.lock().unwrap().len());
+    }
+
+    #[cfg(feature = "events")]
+    #[test]
+    fn test_subscribe() {
+        let mut piece_table = PieceTable::new("abc".to_string());
+        let (id, events) = piece_table.subscribe();
+
+        let indexer = std::thread::spawn(move || events.iter().map(|info| info.inserted).collect::<Vec<_>>());
+        piece_table.insert(3, "d");
+        piece_table.insert(0, "xy");
+        assert!(piece_table.remove_observer(id));
+        piece_table.insert(0, "ignored");
+
+        assert_eq!(vec!["d".to_string(), "xy".to_string()], indexer.join().unwrap());
+    }
+}
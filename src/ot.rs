@@ -0,0 +1,120 @@
+use crate::Edit;
+
+pub fn transform(a: &Edit, b: &Edit) -> (Vec<Edit>, Vec<Edit>) {
+    if matches!(a, Edit::Replace { .. }) || matches!(b, Edit::Replace { .. }) {
+        return transform_batches(&a.primitives(), &b.primitives());
+    }
+    (transform_over(a, b, true), transform_over(b, a, false))
+}
+
+pub fn transform_batches(a: &[Edit], b: &[Edit]) -> (Vec<Edit>, Vec<Edit>) {
+    match (a, b) {
+        ([], _) | (_, []) => (a.to_vec(), b.to_vec()),
+        ([a], [b]) => transform(a, b),
+        ([first, rest @ ..], _) if !rest.is_empty() => {
+            let (mut first, b) = transform_batches(std::slice::from_ref(first), b);
+            let (rest, b) = transform_batches(rest, &b);
+            first.extend(rest);
+            (first, b)
+        }
+        (_, [first, rest @ ..]) => {
+            let (a, mut first) = transform_batches(a, std::slice::from_ref(first));
+            let (a, rest) = transform_batches(&a, rest);
+            first.extend(rest);
+            (a, first)
+        }
+    }
+}
+
+fn transform_over(edit: &Edit, over: &Edit, wins_ties: bool) -> Vec<Edit> {
+    match (edit, over) {
+        (
+            Edit::Insert { offset, text },
+            Edit::Insert {
+                offset: other,
+                text: other_text,
+            },
+        ) => {
+            let shifted = *other < *offset || (*other == *offset && !wins_ties);
+            let offset = if shifted { offset + other_text.len() } else { *offset };
+            vec![Edit::Insert { offset, text: text.clone() }]
+        }
+        (Edit::Insert { offset, text }, Edit::Delete { range }) => {
+            let offset = map_through_delete(*offset, range);
+            vec![Edit::Insert { offset, text: text.clone() }]
+        }
+        (Edit::Delete { range }, Edit::Insert { offset, text }) => {
+            let length = text.len();
+            if *offset <= range.start {
+                vec![Edit::Delete {
+                    range: range.start + length..range.end + length,
+                }]
+            } else if *offset >= range.end {
+                vec![Edit::Delete { range: range.clone() }]
+            } else {
+                vec![
+                    Edit::Delete {
+                        range: offset + length..range.end + length,
+                    },
+                    Edit::Delete { range: range.start..*offset },
+                ]
+            }
+        }
+        (Edit::Delete { range }, Edit::Delete { range: other }) => {
+            let start = map_through_delete(range.start, other);
+            let end = map_through_delete(range.end, other);
+            if start == end {
+                Vec::new()
+            } else {
+                vec![Edit::Delete { range: start..end }]
+            }
+        }
+        (Edit::Replace { .. }, _) | (_, Edit::Replace { .. }) => unreachable!("replacements are transformed as primitives"),
+    }
+}
+
+fn map_through_delete(offset: usize, range: &std::ops::Range<usize>) -> usize {
+    if offset <= range.start {
+        offset
+    } else if offset >= range.end {
+        offset - range.len()
+    } else {
+        range.start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PieceTable;
+
+    fn converges(base: &str, a: &[Edit], b: &[Edit]) -> String {
+        let (a_prime, b_prime) = transform_batches(a, b);
+        let via_a = PieceTable::replay(base.to_string(), &[a, &b_prime].concat()).text();
+        let via_b = PieceTable::replay(base.to_string(), &[b, &a_prime].concat()).text();
+        assert_eq!(via_a, via_b);
+        via_a
+    }
+
+    #[test]
+    fn test_transform() {
+        let insert = |offset: usize, text: &str| Edit::Insert {
+            offset,
+            text: text.to_string(),
+        };
+
+        assert_eq!("ab-x", converges("-x", &[insert(0, "a")], &[insert(0, "b")]));
+        assert_eq!(
+            "2",
+            converges("0123", &[Edit::Delete { range: 0..2 }], &[Edit::Delete { range: 1..4 }, insert(1, "2")])
+        );
+        assert_eq!("[!]", converges("[abc]", &[Edit::Delete { range: 1..4 }], &[insert(2, "!")]));
+        assert_eq!(
+            "one, two and three",
+            converges("one two three", &[insert(3, ","), insert(8, " and")], &[Edit::Delete { range: 0..0 }])
+        );
+
+        let (a_prime, _) = transform(&Edit::Delete { range: 2..6 }, &insert(4, "xy"));
+        assert_eq!(vec![Edit::Delete { range: 6..8 }, Edit::Delete { range: 2..4 }], a_prime);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{LineEnding, PieceTable};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeBounds;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy)]
+pub struct WithLineEnding<'a> {
+    table: &'a PieceTable,
+    line_ending: LineEnding,
+}
+
+impl Display for WithLineEnding<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Some(terminator) = self.line_ending.as_str() else {
+            return Display::fmt(self.table, f);
+        };
+
+        // A CRLF can straddle two pieces, so a trailing CR is remembered until the next piece.
+        let mut after_cr = false;
+        for mut rest in self.table.chunks() {
+            if rest.is_empty() {
+                continue;
+            }
+            if after_cr {
+                rest = rest.strip_prefix('\n').unwrap_or(rest);
+                after_cr = false;
+            }
+            while let Some(index) = rest.find(['\r', '\n']) {
+                f.write_str(&rest[..index])?;
+                f.write_str(terminator)?;
+                let length = if rest[index..].starts_with("\r\n") { 2 } else { 1 };
+                after_cr = &rest[index..] == "\r";
+                rest = &rest[index + length..];
+            }
+            f.write_str(rest)?;
+        }
+        Ok(())
+    }
+}
+
+impl PieceTable {
+    /// Renders the content with every line terminator replaced by `line_ending`, leaving the
+    /// table itself untouched. `Mixed` renders the terminators as stored.
+    pub fn display_with(&self, line_ending: LineEnding) -> WithLineEnding<'_> {
+        WithLineEnding { table: self, line_ending }
+    }
+
+    /// Copies the bytes of `range` into the front of `buffer`, stopping when either runs out, and
+    /// returns how many bytes were written.
+    pub fn copy_range_to(&self, range: impl RangeBounds<usize>, buffer: &mut [u8]) -> usize {
+        let mut written = 0;
+        for chunk in self.chunks_in_range(range) {
+            let length = chunk.len().min(buffer.len() - written);
+            buffer[written..written + length].copy_from_slice(&chunk.as_bytes()[..length]);
+            written += length;
+            if written == buffer.len() {
+                break;
+            }
+        }
+        written
+    }
+
+    /// Appends the text of `range` to `buffer` and returns how many bytes were appended.
+    pub fn read_range_into(&self, range: impl RangeBounds<usize>, buffer: &mut String) -> usize {
+        let length = buffer.len();
+        buffer.extend(self.chunks_in_range(range));
+        buffer.len() - length
+    }
+
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        self.chunks().try_for_each(|chunk| writer.write_all(chunk.as_bytes()))
+    }
+
+    pub fn write_with_line_ending(&self, mut writer: impl Write, line_ending: LineEnding) -> io::Result<()> {
+        write!(writer, "{}", self.display_with(line_ending))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn save_with_line_ending(&self, path: impl AsRef<Path>, line_ending: LineEnding) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_with_line_ending(&mut writer, line_ending)?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_with_line_ending() {
+        let mut piece_table = PieceTable::from("one\r");
+        piece_table.push_str("\ntwo\nthree\r");
+        piece_table.push_str("four\r\n");
+
+        assert_eq!("one\r\ntwo\r\nthree\r\nfour\r\n", piece_table.display_with(LineEnding::Crlf).to_string());
+        assert_eq!("one\ntwo\nthree\nfour\n", piece_table.display_with(LineEnding::Lf).to_string());
+        assert_eq!(piece_table.text(), piece_table.display_with(LineEnding::Mixed).to_string());
+
+        let mut written = Vec::new();
+        piece_table.write_with_line_ending(&mut written, LineEnding::Cr).unwrap();
+        assert_eq!(b"one\rtwo\rthree\rfour\r", written.as_slice());
+        assert_eq!(LineEnding::Mixed, piece_table.detect_line_ending());
+    }
+
+    #[test]
+    fn test_read_range_into() {
+        let mut piece_table = PieceTable::from("hello world");
+        piece_table.insert(5, ",");
+
+        let mut buffer = [0; 8];
+        assert_eq!(8, piece_table.copy_range_to(2.., &mut buffer));
+        assert_eq!(b"llo, wor", &buffer);
+        assert_eq!(3, piece_table.copy_range_to(4..7, &mut buffer));
+        assert_eq!(b"o, ", &buffer[..3]);
+
+        let mut text = String::from("> ");
+        assert_eq!(6, piece_table.read_range_into(..6, &mut text));
+        assert_eq!("> hello,", text);
+        assert_eq!(0, piece_table.read_range_into(20.., &mut text));
+    }
+
+    #[test]
+    fn test_save() {
+        let path = std::env::temp_dir().join(format!("piece_table_save_{}.txt", std::process::id()));
+        let piece_table = PieceTable::from("a\nb\n");
+
+        piece_table.save(&path).unwrap();
+        assert_eq!("a\nb\n", std::fs::read_to_string(&path).unwrap());
+        piece_table.save_with_line_ending(&path, LineEnding::Crlf).unwrap();
+        assert_eq!("a\r\nb\r\n", std::fs::read_to_string(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+use crate::PieceTable;
+use std::ops::Range;
+
+impl PieceTable {
+    /// Returns the runs of non-blank lines, each without the terminator of its last line. Lines
+    /// are read one at a time, so taking the first few paragraphs only reads as far as those.
+    pub fn paragraphs(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let mut line = 0;
+        std::iter::from_fn(move || {
+            let line_count = self.line_count();
+            while line < line_count && self.is_blank_line(line) {
+                line += 1;
+            }
+            if line == line_count {
+                return None;
+            }
+
+            let start = self.line_to_byte(line);
+            let mut end = start;
+            while line < line_count && !self.is_blank_line(line) {
+                end = self.line_range(line).end;
+                line += 1;
+            }
+            Some(start..end)
+        })
+    }
+
+    /// Whether `line` holds nothing but whitespace.
+    pub(crate) fn is_blank_line(&self, line: usize) -> bool {
+        self.chunks_in_range(self.line_range(line)).flat_map(str::chars).all(char::is_whitespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paragraphs() {
+        let mut piece_table = PieceTable::from("first line\nsecond line\n\n  \nnext\r\n\r\nlast");
+        piece_table.insert(0, "\n");
+
+        let paragraphs: Vec<String> = piece_table.paragraphs().map(|paragraph| piece_table.slice(paragraph)).collect();
+        assert_eq!(vec!["first line\nsecond line", "next", "last"], paragraphs);
+        assert_eq!(0, PieceTable::from("\n\n").paragraphs().count());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::merge::{hunks, Hunk};
+use crate::PieceTable;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Patch {
+    pub offset: usize,
+    pub before: String,
+    pub removed: String,
+    pub inserted: String,
+    pub after: String,
+}
+
+impl PieceTable {
+    pub fn make_patches(&self, target: &str, context: usize) -> Vec<Patch> {
+        let text = self.text();
+        let mut merged: Vec<Hunk> = Vec::new();
+        for hunk in hunks(&self.diff_with(target)) {
+            match merged.last_mut() {
+                Some(last) if hunk.range.start - last.range.end <= 2 * context => {
+                    last.text.push_str(&text[last.range.end..hunk.range.start]);
+                    last.text.push_str(&hunk.text);
+                    last.range.end = hunk.range.end;
+                }
+                _ => merged.push(hunk),
+            }
+        }
+
+        merged
+            .into_iter()
+            .map(|hunk| {
+                let mut before = hunk.range.start.saturating_sub(context);
+                while !text.is_char_boundary(before) {
+                    before -= 1;
+                }
+                let mut after = (hunk.range.end + context).min(text.len());
+                while !text.is_char_boundary(after) {
+                    after += 1;
+                }
+                Patch {
+                    offset: hunk.range.start,
+                    before: text[before..hunk.range.start].to_string(),
+                    removed: text[hunk.range.clone()].to_string(),
+                    inserted: hunk.text,
+                    after: text[hunk.range.end..after].to_string(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn fuzzy_patch(&mut self, patches: &[Patch], max_drift: usize) -> Vec<bool> {
+        let mut applied = Vec::with_capacity(patches.len());
+        // The patches are located in a copy of the text made once and kept in step with the edits.
+        let mut text = self.text();
+        self.transaction(|table| {
+            let mut delta: isize = 0;
+            for patch in patches {
+                let expected = patch.offset.saturating_add_signed(delta);
+                match locate(&text, patch, expected, max_drift) {
+                    Some(start) => {
+                        let end = start + patch.removed.len();
+                        let inserted = table.normalize_insert(&patch.inserted).into_owned();
+                        table.replace(start..end, &inserted);
+                        text.replace_range(start..end, &inserted);
+                        delta += start as isize - expected as isize;
+                        delta += inserted.len() as isize - patch.removed.len() as isize;
+                        applied.push(true);
+                    }
+                    None => applied.push(false),
+                }
+            }
+        });
+        applied
+    }
+}
+
+fn locate(text: &str, patch: &Patch, expected: usize, max_drift: usize) -> Option<usize> {
+    let max_trim = patch.before.len().max(patch.after.len());
+
+    // Prefer the full context, then give up context from its outer ends until only the
+    // removed text is left; a pure insertion needs at least some context to anchor it.
+    for trim in 0..=max_trim {
+        let mut before = trim.min(patch.before.len());
+        while !patch.before.is_char_boundary(before) {
+            before += 1;
+        }
+        let mut after = patch.after.len().saturating_sub(trim);
+        while !patch.after.is_char_boundary(after) {
+            after -= 1;
+        }
+        let before = &patch.before[before..];
+        let after = &patch.after[..after];
+        if patch.removed.is_empty() && before.is_empty() && after.is_empty() {
+            break;
+        }
+
+        let needle = [before, patch.removed.as_str(), after].concat();
+        let center = expected.saturating_sub(before.len());
+        let found = (center.saturating_sub(max_drift)..=center.saturating_add(max_drift).min(text.len()))
+            .filter(|&start| text.is_char_boundary(start) && text[start..].starts_with(&needle))
+            .min_by_key(|&start| start.abs_diff(center));
+        if let Some(start) = found {
+            return Some(start + before.len());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_patch() {
+        let base = PieceTable::new("The quick brown fox jumps over the lazy dog.".to_string());
+        let patches = base.make_patches("The quick red fox jumps over the sleepy dog.", 4);
+        assert_eq!(2, patches.len());
+
+        let mut drifted = PieceTable::new("Well, the quick brown fox jumps over the lazy dog.".to_string());
+        assert_eq!(vec![true, true], drifted.fuzzy_patch(&patches, 8));
+        assert_eq!("Well, the quick red fox jumps over the sleepy dog.", drifted.text());
+
+        assert!(drifted.undo());
+        assert_eq!("Well, the quick brown fox jumps over the lazy dog.", drifted.text());
+        assert_eq!(vec![false, false], drifted.fuzzy_patch(&patches, 2));
+        assert_eq!(vec![true, true], drifted.fuzzy_patch(&patches, usize::MAX));
+        assert_eq!("Well, the quick red fox jumps over the sleepy dog.", drifted.text());
+    }
+
+    #[test]
+    fn test_fuzzy_patch_partial_context() {
+        let base = PieceTable::new("one two three four five".to_string());
+        let patches = base.make_patches("one two THREE four five", 5);
+
+        let mut edited = PieceTable::new("one 2 three 4 five".to_string());
+        assert_eq!(vec![true], edited.fuzzy_patch(&patches, 4));
+        assert_eq!("one 2 THREE 4 five", edited.text());
+    }
+}
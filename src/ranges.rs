@@ -0,0 +1,123 @@
+use crate::PieceTable;
+use std::ops::{Bound, Range, RangeBounds};
+
+impl PieceTable {
+    pub fn delete_range(&mut self, range: impl RangeBounds<usize>) {
+        let Range { start, end } = self.resolve_range(range);
+        self.delete(start, end - start);
+    }
+
+    pub fn move_range(&mut self, source: impl RangeBounds<usize>, destination: usize) {
+        let Range { start, end } = self.resolve_range(source);
+        let destination = self.floor_char_boundary(destination);
+        if start == end || (start..=end).contains(&destination) {
+            return;
+        }
+
+        self.transaction(|table| {
+            let change = table.replace_pieces(start, end - start, Vec::new());
+            let moved = change.removed.clone();
+            table.history.record(change);
+
+            let destination = if destination > end { destination - (end - start) } else { destination };
+            table.edit(destination, 0, moved);
+        });
+    }
+
+    pub fn copy_range(&mut self, source: impl RangeBounds<usize>, destination: usize) {
+        let copied = self.pieces_in(self.resolve_range(source));
+        if !copied.is_empty() {
+            let destination = self.floor_char_boundary(destination);
+            self.edit(destination, 0, copied);
+        }
+    }
+
+    /// Inserts a copy of `range` right after itself and returns where the copy landed.
+    pub fn duplicate_range(&mut self, range: impl RangeBounds<usize>) -> Range<usize> {
+        let Range { start, end } = self.resolve_range(range);
+        self.copy_range(start..end, end);
+        end..end + (end - start)
+    }
+
+    pub(crate) fn resolve_range(&self, range: impl RangeBounds<usize>) -> Range<usize> {
+        let length = self.length();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => length,
+        };
+        let end = if end > start { self.ceil_char_boundary(end) } else { 0 };
+        let start = self.floor_char_boundary(start);
+        start..end.max(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delete_range() {
+        let mut piece_table = PieceTable::from("0123456789");
+        piece_table.delete_range(8..);
+        piece_table.delete_range(..=1);
+        piece_table.delete_range(2..4);
+        assert_eq!("2367", piece_table.text());
+
+        assert_eq!("36", piece_table.slice(1..=2));
+        assert_eq!("2367", piece_table.slice(..));
+        assert_eq!("7", piece_table.slice(3..));
+        assert_eq!(3, piece_table.history_len());
+        piece_table.delete_range(10..);
+        assert_eq!(3, piece_table.history_len());
+    }
+
+    #[test]
+    fn test_move_range() {
+        let mut piece_table = PieceTable::new("one two three".to_string());
+        piece_table.insert(3, ",");
+
+        piece_table.move_range(0..5, 14);
+        assert_eq!("two threeone, ", piece_table.text());
+        piece_table.move_range(3..9, 0);
+        assert_eq!(" threetwoone, ", piece_table.text());
+        assert_eq!(",", piece_table.add.as_str());
+
+        piece_table.move_range(4..8, 6);
+        assert_eq!(" threetwoone, ", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("two threeone, ", piece_table.text());
+    }
+
+    #[test]
+    fn test_copy_range() {
+        let mut piece_table = PieceTable::new("line one\n".to_string());
+        piece_table.insert(4, "!");
+
+        piece_table.copy_range(0..10, 10);
+        assert_eq!("line! one\nline! one\n", piece_table.text());
+        assert_eq!("!", piece_table.add.as_str());
+
+        piece_table.copy_range(2..7, 0);
+        assert_eq!("ne! oline! one\nline! one\n", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("line! one\nline! one\n", piece_table.text());
+    }
+
+    #[test]
+    fn test_duplicate_range() {
+        let mut piece_table = PieceTable::from("ab");
+        piece_table.push_str("cd");
+
+        assert_eq!(4..7, piece_table.duplicate_range(1..4));
+        assert_eq!("abcdbcd", piece_table.text());
+        assert_eq!("cd", piece_table.add.as_str());
+        assert_eq!(7..7, piece_table.duplicate_range(7..));
+        assert_eq!(2, piece_table.history_len());
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{Edit, PieceTable, SearchMatch};
+use regex_automata::hybrid::dfa;
+use regex_automata::hybrid::regex::{Cache, Regex};
+use regex_automata::hybrid::LazyStateID;
+use regex_automata::meta;
+use regex_automata::util::start;
+use regex_automata::{Anchored, Input, MatchError};
+use std::fmt::{Display, Formatter};
+use std::ops::{Range, RangeBounds};
+
+#[derive(Debug)]
+pub enum RegexError {
+    Build(Box<dyn std::error::Error + Send + Sync>),
+    Search(MatchError),
+}
+
+impl Display for RegexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegexError::Build(error) => write!(f, "invalid pattern: {error}"),
+            RegexError::Search(error) => write!(f, "search failed: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+/// A compiled pattern driven one byte at a time, so it can run across piece boundaries: a forward
+/// scan finds where the leftmost match ends and an anchored reverse scan from there finds where it
+/// starts.
+pub(crate) struct Searcher {
+    regex: Regex,
+    cache: Cache,
+}
+
+impl Searcher {
+    pub(crate) fn new(pattern: &str) -> Result<Self, RegexError> {
+        let regex = Regex::builder()
+            .dfa(dfa::Config::new().unicode_word_boundary(true))
+            .build(pattern)
+            .map_err(|error| RegexError::Build(Box::new(error)))?;
+        let cache = regex.create_cache();
+        Ok(Searcher { regex, cache })
+    }
+
+    pub(crate) fn find_at(&mut self, table: &PieceTable, from: usize) -> Result<Option<Range<usize>>, RegexError> {
+        let Some(end) = self.find_end(table, from)? else {
+            return Ok(None);
+        };
+        let start = self.find_start(table, from, end)?;
+        Ok(Some(start..end))
+    }
+
+    fn find_end(&mut self, table: &PieceTable, from: usize) -> Result<Option<usize>, RegexError> {
+        let forward = self.regex.forward();
+        let cache = self.cache.forward_mut();
+        let config = start::Config::new()
+            .anchored(Anchored::No)
+            .look_behind(from.checked_sub(1).and_then(|offset| byte_at(table, offset)));
+        let mut state = forward.start_state(cache, &config).map_err(|_| gave_up(from))?;
+
+        let mut end = None;
+        let mut at = from;
+        for chunk in table.chunks_in_range(from..) {
+            for &byte in chunk.as_bytes() {
+                state = forward.next_state(cache, state, byte).map_err(|_| gave_up(at))?;
+                if let Some(done) = check(state, byte, at, &mut end)? {
+                    return Ok(done);
+                }
+                at += 1;
+            }
+        }
+        state = forward.next_eoi_state(cache, state).map_err(|_| gave_up(at))?;
+        if state.is_match() {
+            end = Some(at);
+        }
+        Ok(end)
+    }
+
+    fn find_start(&mut self, table: &PieceTable, from: usize, end: usize) -> Result<usize, RegexError> {
+        let reverse = self.regex.reverse();
+        let cache = self.cache.reverse_mut();
+        let config = start::Config::new().anchored(Anchored::Yes).look_behind(byte_at(table, end));
+        let mut state = reverse.start_state(cache, &config).map_err(|_| gave_up(end))?;
+
+        let mut start = None;
+        let mut at = end;
+        for chunk in table.chunks_in_range(from..end).rev() {
+            for &byte in chunk.as_bytes().iter().rev() {
+                state = reverse.next_state(cache, state, byte).map_err(|_| gave_up(at))?;
+                if let Some(done) = check(state, byte, at, &mut start)? {
+                    return Ok(done.unwrap_or(end));
+                }
+                at -= 1;
+            }
+        }
+        state = match from.checked_sub(1).and_then(|offset| byte_at(table, offset)) {
+            Some(byte) => reverse.next_state(cache, state, byte).map_err(|_| gave_up(at))?,
+            None => reverse.next_eoi_state(cache, state).map_err(|_| gave_up(at))?,
+        };
+        if state.is_match() {
+            start = Some(at);
+        }
+        Ok(start.unwrap_or(end))
+    }
+}
+
+/// Records a match that ends (or starts, in reverse) at `at`, and reports when the scan is over.
+fn check(state: LazyStateID, byte: u8, at: usize, found: &mut Option<usize>) -> Result<Option<Option<usize>>, RegexError> {
+    if state.is_match() {
+        *found = Some(at);
+    } else if state.is_dead() {
+        return Ok(Some(*found));
+    } else if state.is_quit() {
+        return Err(RegexError::Search(MatchError::quit(byte, at)));
+    }
+    Ok(None)
+}
+
+fn gave_up(offset: usize) -> RegexError {
+    RegexError::Search(MatchError::gave_up(offset))
+}
+
+fn byte_at(table: &PieceTable, offset: usize) -> Option<u8> {
+    let (piece, _, running_total) = table.piece_at(offset)?;
+    table.piece_text(&piece).as_bytes().get(offset - running_total).copied()
+}
+
+impl PieceTable {
+    /// Returns the leftmost match of `pattern` starting at or after `from`.
+    ///
+    /// The search runs over the pieces without joining them. Unicode word boundaries are handled
+    /// for ASCII text only; meeting a non-ASCII byte next to one gives up with
+    /// `RegexError::Search`, and `(?-u:\b)` avoids that.
+    pub fn find_regex(&self, pattern: &str, from: usize) -> Result<Option<Range<usize>>, RegexError> {
+        Searcher::new(pattern)?.find_at(self, self.floor_char_boundary(from))
+    }
+
+    /// Replaces every match of `pattern`, expanding `$1`, `${name}` and `$$` in `replacement`, as
+    /// a single undoable batch, and returns how many matches were replaced.
+    pub fn regex_replace_all(&mut self, pattern: &str, replacement: &str) -> Result<usize, RegexError> {
+        let matches = self.regex_matches(pattern)?;
+        if matches.is_empty() {
+            return Ok(0);
+        }
+
+        let regex = meta::Regex::new(pattern).map_err(|error| RegexError::Build(Box::new(error)))?;
+        let mut captures = regex.create_captures();
+        let mut edits = Vec::with_capacity(matches.len());
+        for range in &matches {
+            // Look-around assertions never see further than one char, so that much context is
+            // enough to capture the same groups the streaming search matched.
+            let window = self.prev_char_offset(range.start).unwrap_or(0)..self.next_char_offset(range.end).unwrap_or(range.end);
+            let haystack = self.slice_cow(window.clone());
+            let input = Input::new(haystack.as_ref())
+                .span(range.start - window.start..range.end - window.start)
+                .anchored(Anchored::Yes);
+            regex.search_captures(&input, &mut captures);
+
+            let mut text = String::new();
+            captures.interpolate_string_into(&haystack, replacement, &mut text);
+            edits.push(Edit::Replace { range: range.clone(), text });
+        }
+        self.apply_edits(&edits).expect("regex matches never overlap");
+        Ok(matches.len())
+    }
+
+    /// Returns every match of `pattern` that lies within `range`. Assertions such as `^` and `\b`
+    /// still see the text just outside the range.
+    pub fn find_regex_in(&self, range: impl RangeBounds<usize>, pattern: &str) -> Result<Vec<SearchMatch>, RegexError> {
+        let regex = meta::Regex::new(pattern).map_err(|error| RegexError::Build(Box::new(error)))?;
+        let range = self.resolve_range(range);
+        let window = self.prev_char_offset(range.start).unwrap_or(0)..self.next_char_offset(range.end).unwrap_or(range.end);
+        let haystack = self.slice_cow(window.clone());
+        let input = Input::new(haystack.as_ref()).span(range.start - window.start..range.end - window.start);
+        Ok(regex
+            .find_iter(input)
+            .map(|found| self.search_match(window.start + found.start()..window.start + found.end()))
+            .collect())
+    }
+
+    /// Returns every non-overlapping match of `pattern`, in order.
+    pub fn regex_matches(&self, pattern: &str) -> Result<Vec<Range<usize>>, RegexError> {
+        let mut searcher = Searcher::new(pattern)?;
+        let mut matches: Vec<Range<usize>> = Vec::new();
+        let mut from = 0;
+        while let Some(found) = searcher.find_at(self, from)? {
+            let after_previous = matches.last().is_some_and(|last| last.end == found.start);
+            if !found.is_empty() {
+                from = found.end;
+            } else {
+                match self.next_char_offset(found.end) {
+                    Some(next) => from = next,
+                    None if !after_previous => {
+                        matches.push(found);
+                        break;
+                    }
+                    None => break,
+                }
+                if after_previous {
+                    continue;
+                }
+            }
+            matches.push(found);
+        }
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_regex() {
+        let mut piece_table = PieceTable::from("let x = 10;\nlet yy = 2");
+        piece_table.insert(10, "0");
+        piece_table.insert(4, "_");
+        piece_table.delete(4, 1);
+
+        assert_eq!(Some(8..11), piece_table.find_regex(r"\d+", 0).unwrap());
+        assert_eq!(Some(9..11), piece_table.find_regex(r"\d+", 9).unwrap());
+        assert_eq!(Some(17..19), piece_table.find_regex(r"(?m)\b[a-z]{2}\b", 5).unwrap());
+        assert_eq!(Some(13..16), piece_table.find_regex(r"(?m)^let", 1).unwrap());
+        assert_eq!(None, piece_table.find_regex(r"(?m)^let", 14).unwrap());
+        assert!(matches!(piece_table.find_regex("(", 0), Err(RegexError::Build(_))));
+    }
+
+    #[test]
+    fn test_regex_matches() {
+        let mut piece_table = PieceTable::from("baaab");
+        piece_table.insert(2, "a");
+        assert_eq!(vec![0..0, 1..5, 6..6], piece_table.regex_matches("a*").unwrap());
+        assert_eq!(vec![1..5], piece_table.regex_matches("a+").unwrap());
+        assert_eq!(vec![0..1, 5..6], piece_table.regex_matches("b").unwrap());
+    }
+
+    #[test]
+    fn test_find_regex_in() {
+        let piece_table = PieceTable::from("fn main() {\n    let id = 42;\n}\nfn other() {}");
+        let found = piece_table.find_regex_in(12..40, r"\bfn\b|\d+").unwrap();
+        assert_eq!(
+            vec![
+                SearchMatch {
+                    range: 25..27,
+                    start: (1, 13),
+                    end: (1, 15),
+                },
+                SearchMatch {
+                    range: 31..33,
+                    start: (3, 0),
+                    end: (3, 2),
+                },
+            ],
+            found
+        );
+        assert!(piece_table.find_regex_in(26..27, r"\b\d").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_regex_replace_all() {
+        let mut piece_table = PieceTable::from("name = value; key=other");
+        piece_table.insert(7, "a ");
+
+        assert_eq!(2, piece_table.regex_replace_all(r"(\w+)\s*=\s*(?<v>\w+)", "${v}: $1 $$").unwrap());
+        assert_eq!("a: name $ value; other: key $", piece_table.text());
+        assert_eq!(0, piece_table.regex_replace_all("missing", "x").unwrap());
+
+        assert!(piece_table.undo());
+        assert_eq!("name = a value; key=other", piece_table.text());
+        assert_eq!(5, piece_table.regex_replace_all("(?m)^|a|$", "|").unwrap());
+        assert_eq!("|n|me = | v|lue; key=other|", piece_table.text());
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{Piece, PieceTable};
+
+impl PieceTable {
+    pub fn retain(&mut self, mut keep: impl FnMut(char) -> bool) {
+        let mut retained: Vec<Piece> = Vec::new();
+        for piece in &self.pieces {
+            let text = self.piece_text(piece);
+            for (index, ch) in text.char_indices().filter(|&(_, ch)| keep(ch)) {
+                let offset = piece.offset + index;
+                let line_breaks = usize::from(ch == '\n');
+                match retained.last_mut() {
+                    Some(last) if last.buffer == piece.buffer && last.offset + last.length == offset => {
+                        last.length += ch.len_utf8();
+                        last.line_breaks += line_breaks;
+                    }
+                    _ => retained.push(Piece::new(piece.buffer, ch.len_utf8(), offset, line_breaks)),
+                }
+            }
+        }
+
+        if retained != self.pieces {
+            let length = self.length();
+            self.edit(0, length, retained);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retain() {
+        let mut piece_table = PieceTable::new("line\u{7}one\r\n".to_string());
+        piece_table.insert(4, "\u{1b}[0m two");
+
+        piece_table.retain(|ch| !ch.is_control());
+        assert_eq!("line[0m twoone", piece_table.text());
+        assert_eq!(3, piece_table.pieces.len());
+
+        piece_table.retain(|_| true);
+        assert_eq!(2, piece_table.history_len());
+        assert!(piece_table.undo());
+        assert_eq!("line\u{1b}[0m two\u{7}one\r\n", piece_table.text());
+    }
+}
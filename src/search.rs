@@ -0,0 +1,455 @@
+use crate::{CharsAt, Edit, PieceTable};
+use std::ops::{Range, RangeBounds};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaseMatching {
+    #[default]
+    Sensitive,
+    IgnoreAsciiCase,
+    IgnoreCase,
+    /// Ignores case unless the needle contains an uppercase letter.
+    Smart,
+}
+
+/// A match located both as bytes and as `(line, column)` positions, columns counted in chars.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchMatch {
+    pub range: Range<usize>,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl PieceTable {
+    /// Returns the offset of the first occurrence of `needle` starting at or after `from`.
+    pub fn find(&self, needle: &str, from: usize) -> Option<usize> {
+        let start = self.floor_char_boundary(from);
+        if needle.is_empty() {
+            return Some(start);
+        }
+        self.find_within(needle, start..)
+    }
+
+    /// Returns the offset of the first occurrence of a non-empty `needle` that lies entirely
+    /// within `range`, looking at no text outside it.
+    pub(crate) fn find_within(&self, needle: &str, range: impl RangeBounds<usize>) -> Option<usize> {
+        let Range { start, end } = self.resolve_range(range);
+
+        // Each chunk is searched in place; matches that straddle a piece boundary are completed
+        // from the prefix automaton's state at the end of the previous chunk, so nothing is copied.
+        let prefixes = Prefixes::new(needle.bytes());
+        let mut state = 0;
+        let mut offset = start;
+        for chunk in self.chunks_in_range(start..end) {
+            let bytes = chunk.as_bytes();
+            if let Some(length) = prefixes.complete(state, bytes.iter().copied()) {
+                return Some(offset + length - needle.len());
+            }
+            if let Some(index) = chunk.find(needle) {
+                return Some(offset + index);
+            }
+            state = prefixes.state_after(state, bytes.iter().copied(), bytes.len());
+            offset += bytes.len();
+        }
+        None
+    }
+
+    pub fn contains(&self, needle: &str) -> bool {
+        self.find(needle, 0).is_some()
+    }
+
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        let mut rest = prefix.as_bytes();
+        for chunk in self.chunks() {
+            let length = chunk.len().min(rest.len());
+            if chunk.as_bytes()[..length] != rest[..length] {
+                return false;
+            }
+            rest = &rest[length..];
+            if rest.is_empty() {
+                return true;
+            }
+        }
+        rest.is_empty()
+    }
+
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        let mut rest = suffix.as_bytes();
+        for chunk in self.chunks().rev() {
+            let length = chunk.len().min(rest.len());
+            if chunk.as_bytes()[chunk.len() - length..] != rest[rest.len() - length..] {
+                return false;
+            }
+            rest = &rest[..rest.len() - length];
+            if rest.is_empty() {
+                return true;
+            }
+        }
+        rest.is_empty()
+    }
+
+    /// Splits the text on a `char` or `&str` separator like `str::split`, yielding byte ranges. An
+    /// empty separator yields the whole text as one range.
+    pub fn split(&self, separator: impl Into<String>) -> Split<'_> {
+        Split {
+            table: self,
+            separator: separator.into(),
+            start: 0,
+            finished: false,
+        }
+    }
+
+    /// Returns every non-overlapping occurrence of `needle` that lies entirely within `range`.
+    pub fn find_in(&self, range: impl RangeBounds<usize>, needle: &str) -> Vec<SearchMatch> {
+        let range = self.resolve_range(range);
+        let mut matches = Vec::new();
+        if needle.is_empty() {
+            return matches;
+        }
+
+        let mut from = range.start;
+        while let Some(start) = self.find_within(needle, from..range.end) {
+            from = start + needle.len();
+            matches.push(self.search_match(start..from));
+        }
+        matches
+    }
+
+    pub(crate) fn search_match(&self, range: Range<usize>) -> SearchMatch {
+        SearchMatch {
+            start: self.position_of(range.start),
+            end: self.position_of(range.end),
+            range,
+        }
+    }
+
+    /// Replaces every non-overlapping occurrence of `needle` as one undoable batch and returns how
+    /// many were replaced. An empty needle replaces nothing.
+    pub fn replace_all(&mut self, needle: &str, replacement: &str) -> usize {
+        if needle.is_empty() {
+            return 0;
+        }
+
+        let mut edits = Vec::new();
+        let mut from = 0;
+        while let Some(start) = self.find(needle, from) {
+            from = start + needle.len();
+            edits.push(Edit::Replace {
+                range: start..from,
+                text: replacement.to_string(),
+            });
+        }
+        if !edits.is_empty() {
+            self.apply_edits(&edits).expect("occurrences never overlap");
+        }
+        edits.len()
+    }
+
+    /// Returns the range of the first match of `needle` starting at or after `from`. Ignoring case
+    /// compares lowercase mappings char by char, so a match can differ in length from `needle`.
+    pub fn find_with_case(&self, needle: &str, from: usize, case: CaseMatching) -> Option<Range<usize>> {
+        let case = match case {
+            CaseMatching::Smart if needle.chars().any(char::is_uppercase) => CaseMatching::Sensitive,
+            CaseMatching::Smart => CaseMatching::IgnoreCase,
+            case => case,
+        };
+        let needle: Vec<char> = match case {
+            CaseMatching::Sensitive => {
+                return self.find(needle, from).map(|start| start..start + needle.len());
+            }
+            CaseMatching::IgnoreCase => needle.chars().flat_map(char::to_lowercase).collect(),
+            _ => needle.chars().collect(),
+        };
+
+        let mut chars = self.chars_at(from);
+        loop {
+            let start = chars.offset();
+            if let Some(end) = match_at(chars.clone(), &needle, case) {
+                return Some(start..end);
+            }
+            chars.next()?;
+        }
+    }
+
+    /// Returns the offset of the last occurrence of `needle` that ends at or before `from`.
+    pub fn rfind(&self, needle: &str, from: usize) -> Option<usize> {
+        let end = self.floor_char_boundary(from);
+        if needle.is_empty() {
+            return Some(end);
+        }
+
+        let prefixes = Prefixes::new(needle.bytes().rev());
+        let mut state = 0;
+        let mut offset = end;
+        for chunk in self.chunks_in_range(..end).rev() {
+            let bytes = chunk.as_bytes();
+            if let Some(length) = prefixes.complete(state, bytes.iter().rev().copied()) {
+                return Some(offset - length);
+            }
+            if let Some(index) = chunk.rfind(needle) {
+                return Some(offset - bytes.len() + index);
+            }
+            state = prefixes.state_after(state, bytes.iter().rev().copied(), bytes.len());
+            offset -= bytes.len();
+        }
+        None
+    }
+}
+
+/// The byte ranges between occurrences of a separator, as returned by [`PieceTable::split`].
+#[derive(Debug, Clone)]
+pub struct Split<'a> {
+    table: &'a PieceTable,
+    separator: String,
+    start: usize,
+    finished: bool,
+}
+
+impl Iterator for Split<'_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let start = self.start;
+        let found = Some(&self.separator).filter(|separator| !separator.is_empty());
+        match found.and_then(|separator| self.table.find(separator, start)) {
+            Some(end) => {
+                self.start = end + self.separator.len();
+                Some(start..end)
+            }
+            None => {
+                self.finished = true;
+                Some(start..self.table.length())
+            }
+        }
+    }
+}
+
+/// A Knuth-Morris-Pratt automaton over the needle's bytes, in the order they are fed. Its state is
+/// the length of the longest needle prefix that ends at the current position.
+struct Prefixes {
+    needle: Vec<u8>,
+    failure: Vec<usize>,
+}
+
+impl Prefixes {
+    fn new(needle: impl Iterator<Item = u8>) -> Self {
+        let needle: Vec<u8> = needle.collect();
+        let mut failure = vec![0; needle.len()];
+        let mut length = 0;
+        for index in 1..needle.len() {
+            while length > 0 && needle[index] != needle[length] {
+                length = failure[length - 1];
+            }
+            if needle[index] == needle[length] {
+                length += 1;
+            }
+            failure[index] = length;
+        }
+        Prefixes { needle, failure }
+    }
+
+    fn step(&self, mut state: usize, byte: u8) -> usize {
+        if state == self.needle.len() {
+            state = self.failure[state - 1];
+        }
+        while state > 0 && self.needle[state] != byte {
+            state = self.failure[state - 1];
+        }
+        if self.needle[state] == byte {
+            state + 1
+        } else {
+            0
+        }
+    }
+
+    /// Feeds a chunk to the prefix carried over from earlier chunks and returns how many of its
+    /// bytes complete the needle. Stops once every remaining candidate starts inside the chunk.
+    fn complete(&self, mut state: usize, bytes: impl Iterator<Item = u8>) -> Option<usize> {
+        for (consumed, byte) in (1..).zip(bytes) {
+            if state < consumed {
+                break;
+            }
+            state = self.step(state, byte);
+            if state == self.needle.len() {
+                return Some(consumed);
+            }
+        }
+        None
+    }
+
+    /// The state once a chunk without a match has been fed. Only the last `needle.len() - 1` bytes
+    /// can start a prefix, so a long chunk is fed from there with a fresh state.
+    fn state_after(&self, state: usize, bytes: impl Iterator<Item = u8>, length: usize) -> usize {
+        let keep = self.needle.len() - 1;
+        let (state, skip) = if length >= keep { (0, length - keep) } else { (state, 0) };
+        bytes.skip(skip).fold(state, |state, byte| self.step(state, byte))
+    }
+}
+
+fn match_at(mut chars: CharsAt<'_>, needle: &[char], case: CaseMatching) -> Option<usize> {
+    let mut matched = 0;
+    while matched < needle.len() {
+        let ch = chars.next()?;
+        if case == CaseMatching::IgnoreCase {
+            for lower in ch.to_lowercase() {
+                if needle.get(matched) != Some(&lower) {
+                    return None;
+                }
+                matched += 1;
+            }
+        } else if ch.eq_ignore_ascii_case(&needle[matched]) {
+            matched += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(chars.offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let mut piece_table = PieceTable::from("needle in a haystack, neexle");
+        piece_table.insert(24, "ed");
+        piece_table.delete(26, 2);
+        piece_table.insert(2, "e");
+        piece_table.delete(2, 1);
+        assert_eq!("needle in a haystack, needle", piece_table.text());
+
+        assert_eq!(Some(0), piece_table.find("needle", 0));
+        assert_eq!(Some(22), piece_table.find("needle", 1));
+        assert_eq!(Some(24), piece_table.find("edl", 5));
+        assert_eq!(None, piece_table.find("needle", 23));
+        assert_eq!(Some(7), piece_table.find("", 7));
+        assert_eq!(None, piece_table.find("missing", 0));
+    }
+
+    #[test]
+    fn test_rfind() {
+        let mut piece_table = PieceTable::from("abcXabc");
+        piece_table.insert(5, "-");
+        piece_table.insert(2, "-");
+        piece_table.delete(2, 1);
+        piece_table.delete(5, 1);
+        assert_eq!("abcXabc", piece_table.text());
+
+        assert_eq!(Some(4), piece_table.rfind("abc", 7));
+        assert_eq!(Some(0), piece_table.rfind("abc", 6));
+        assert_eq!(Some(2), piece_table.rfind("cXa", 7));
+        assert_eq!(None, piece_table.rfind("abc", 2));
+        assert_eq!(Some(3), piece_table.rfind("", 3));
+    }
+
+    #[test]
+    fn test_find_with_case() {
+        let mut piece_table = PieceTable::from("Hello WORLD, hello Ärger");
+        piece_table.insert(8, "-");
+        assert_eq!("Hello WO-RLD, hello Ärger", piece_table.text());
+
+        assert_eq!(Some(14..19), piece_table.find_with_case("hello", 1, CaseMatching::Sensitive));
+        assert_eq!(Some(0..5), piece_table.find_with_case("HELLO", 0, CaseMatching::IgnoreAsciiCase));
+        assert_eq!(Some(7..9), piece_table.find_with_case("o-", 0, CaseMatching::IgnoreCase));
+        assert_eq!(None, piece_table.find_with_case("ärger", 0, CaseMatching::IgnoreAsciiCase));
+        assert_eq!(Some(20..26), piece_table.find_with_case("ärger", 0, CaseMatching::IgnoreCase));
+        assert_eq!(Some(0..5), piece_table.find_with_case("hello", 0, CaseMatching::Smart));
+        assert_eq!(None, piece_table.find_with_case("Hello", 1, CaseMatching::Smart));
+        assert_eq!(Some(3..3), piece_table.find_with_case("", 3, CaseMatching::IgnoreCase));
+    }
+
+    #[test]
+    fn test_starts_with_ends_with() {
+        let mut piece_table = PieceTable::from("hello world");
+        piece_table.insert(5, ",");
+        piece_table.insert(0, ">");
+        assert_eq!(">hello, world", piece_table.text());
+
+        assert!(piece_table.starts_with(">hel"));
+        assert!(piece_table.starts_with(""));
+        assert!(!piece_table.starts_with(">help"));
+        assert!(piece_table.ends_with("o, world"));
+        assert!(!piece_table.ends_with("x, world"));
+        assert!(!piece_table.ends_with("<>hello, world"));
+        assert!(piece_table.contains("o, w"));
+        assert!(!piece_table.contains("o,  w"));
+    }
+
+    #[test]
+    fn test_split() {
+        let mut piece_table = PieceTable::from("a,b,,c");
+        piece_table.insert(6, ",");
+        piece_table.insert(3, "b");
+        assert_eq!("a,bb,,c,", piece_table.text());
+
+        let fields: Vec<String> = piece_table.split(',').map(|range| piece_table.slice(range)).collect();
+        assert_eq!(vec!["a", "bb", "", "c", ""], fields);
+        assert_eq!(vec![0..4, 6..8], piece_table.split(",,").collect::<Vec<_>>());
+        assert_eq!(vec![0..8], piece_table.split("").collect::<Vec<_>>());
+        assert_eq!(vec![0..0], PieceTable::default().split(',').collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_find_in() {
+        let mut piece_table = PieceTable::from("let a = 1;\nlet b = a;\nlet c = a + b;");
+        piece_table.insert(15, " ");
+        assert_eq!("let a = 1;\nlet  b = a;\nlet c = a + b;", piece_table.text());
+
+        let found = piece_table.find_in(11..33, "a");
+        assert_eq!(
+            vec![
+                SearchMatch {
+                    range: 20..21,
+                    start: (1, 9),
+                    end: (1, 10),
+                },
+                SearchMatch {
+                    range: 31..32,
+                    start: (2, 8),
+                    end: (2, 9),
+                },
+            ],
+            found
+        );
+        assert!(piece_table.find_in(..4, "let a").is_empty());
+        assert_eq!(3, piece_table.find_in(.., "let").len());
+        assert!(piece_table.find_in(.., "").is_empty());
+    }
+
+    #[test]
+    fn test_replace_all() {
+        let mut piece_table = PieceTable::from("aaa foo aXa");
+        piece_table.delete(9, 1);
+        piece_table.insert(9, "a");
+
+        assert_eq!(2, piece_table.replace_all("aa", "b"));
+        assert_eq!("ba foo ba", piece_table.text());
+        assert_eq!(0, piece_table.replace_all("zz", "b"));
+        assert_eq!(0, piece_table.replace_all("", "b"));
+        assert_eq!(1, piece_table.replace_all(" foo ", ""));
+        assert_eq!("baba", piece_table.text());
+
+        assert!(piece_table.undo());
+        assert!(piece_table.undo());
+        assert_eq!("aaa foo aaa", piece_table.text());
+    }
+
+    #[test]
+    fn test_find_across_pieces() {
+        let mut piece_table = PieceTable::default();
+        for ch in "abaabaababaabx".chars().rev() {
+            piece_table.insert_char(0, ch);
+        }
+        assert_eq!(15, piece_table.pieces.len());
+
+        assert_eq!(Some(6), piece_table.find("ababaab", 0));
+        assert_eq!(Some(8), piece_table.find("abaabx", 0));
+        assert_eq!(Some(6), piece_table.rfind("ababaab", 14));
+        assert_eq!(Some(3), piece_table.rfind("aba", 7));
+    }
+}
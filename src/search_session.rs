@@ -0,0 +1,140 @@
+use crate::{ChangeInfo, ObserverId, PieceTable};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// Keeps every occurrence of a needle, overlapping ones included, up to date across edits. Change
+/// notifications only drop the matches they touch; the edited regions are searched again lazily by
+/// [`SearchSession::matches`].
+#[derive(Debug)]
+pub struct SearchSession {
+    needle: String,
+    matches: Vec<Range<usize>>,
+    dirty: Vec<Range<usize>>,
+    observer: ObserverId,
+    received: Arc<Mutex<Vec<ChangeInfo>>>,
+}
+
+impl SearchSession {
+    pub fn new(table: &mut PieceTable, needle: &str) -> Self {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        let observer = table.on_change(move |info| sink.lock().expect("search session lock").push(info.clone()));
+
+        let mut session = SearchSession {
+            needle: needle.to_string(),
+            matches: Vec::new(),
+            dirty: std::iter::once(0..table.length()).collect(),
+            observer,
+            received,
+        };
+        session.rescan(table);
+        session
+    }
+
+    pub fn needle(&self) -> &str {
+        &self.needle
+    }
+
+    /// Returns the matches in `table`, which must be the table the session was created on.
+    pub fn matches(&mut self, table: &PieceTable) -> &[Range<usize>] {
+        let changes = std::mem::take(&mut *self.received.lock().expect("search session lock"));
+        for change in &changes {
+            self.invalidate(change);
+        }
+        self.rescan(table);
+        &self.matches
+    }
+
+    /// Stops listening for changes on `table`.
+    pub fn close(self, table: &mut PieceTable) -> bool {
+        table.remove_observer(self.observer)
+    }
+
+    fn invalidate(&mut self, change: &ChangeInfo) {
+        if change.deleted == 0 && change.inserted.is_empty() {
+            return;
+        }
+        let removed_end = change.offset + change.deleted;
+        let shift = |offset: usize| offset - change.deleted + change.inserted.len();
+
+        self.matches.retain_mut(|range| {
+            if range.end <= change.offset {
+                true
+            } else if range.start >= removed_end {
+                *range = shift(range.start)..shift(range.end);
+                true
+            } else {
+                false
+            }
+        });
+        for range in &mut self.dirty {
+            if range.end < change.offset {
+                continue;
+            }
+            if range.start > removed_end {
+                *range = shift(range.start)..shift(range.end);
+            } else {
+                range.start = range.start.min(change.offset);
+                range.end = shift(range.end.max(removed_end));
+            }
+        }
+        self.dirty.push(change.offset..change.offset + change.inserted.len());
+    }
+
+    // A match is new if it overlaps a dirty range, or spans the point left behind by a deletion.
+    fn rescan(&mut self, table: &PieceTable) {
+        let Some(first) = self.needle.chars().next() else {
+            self.dirty.clear();
+            return;
+        };
+        let length = self.needle.len();
+        let mut found = Vec::new();
+        for range in self.dirty.drain(..) {
+            // Only matches starting before the end of the range count, so none can end past this.
+            let window_end = range.end.saturating_add(length - 1);
+            let mut from = range.start.saturating_sub(length - 1);
+            while let Some(start) = table.find_within(&self.needle, from..window_end) {
+                if start >= range.end {
+                    break;
+                }
+                if start + length > range.start {
+                    found.push(start..start + length);
+                }
+                from = start + first.len_utf8();
+            }
+        }
+        if !found.is_empty() {
+            self.matches.extend(found);
+            self.matches.sort_by_key(|range| range.start);
+            self.matches.dedup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_session() {
+        let mut piece_table = PieceTable::from("abab cab ab");
+        let mut session = SearchSession::new(&mut piece_table, "ab");
+        assert_eq!(&[0..2, 2..4, 6..8, 9..11], session.matches(&piece_table));
+
+        piece_table.insert(3, "x");
+        piece_table.delete(6, 1);
+        assert_eq!("abaxb ab ab", piece_table.text());
+        assert_eq!(&[0..2, 6..8, 9..11], session.matches(&piece_table));
+
+        piece_table.insert(9, "a");
+        piece_table.delete(4, 2);
+        piece_table.replace(0..1, "A");
+        assert_eq!("Abaxab aab", piece_table.text());
+        assert_eq!(&[4..6, 8..10], session.matches(&piece_table));
+
+        piece_table.undo();
+        assert_eq!("abaxab aab", piece_table.text());
+        assert_eq!(&[0..2, 4..6, 8..10], session.matches(&piece_table));
+        assert!(session.close(&mut piece_table));
+    }
+}
@@ -0,0 +1,405 @@
+use crate::dirty::ChangeLog;
+use crate::hash::HashCache;
+use crate::history::History;
+use crate::observe::Observers;
+use crate::widths::LineWidths;
+use crate::{Buffer, Piece, PieceTable};
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::sync::{Arc, Weak};
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub(crate) original: Arc<str>,
+    pub(crate) add: Arc<String>,
+    pub(crate) pieces: Arc<[Piece]>,
+    length: usize,
+    add_ancestry: Vec<(Weak<String>, usize)>,
+}
+
+impl Snapshot {
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &str> {
+        self.pieces.iter().map(|piece| piece.text(&self.original, &self.add))
+    }
+
+    pub fn text(&self) -> String {
+        self.chunks().collect()
+    }
+
+    /// How many leading bytes of the add buffers of `self` and `other` are known to be the same:
+    /// all of them for one buffer, or as much as they held when one was copied from the other or
+    /// both from a common ancestor. Forks of a table each append to their own copy.
+    pub(crate) fn shared_add_prefix(&self, other: &Snapshot) -> usize {
+        if Arc::ptr_eq(&self.add, &other.add) {
+            return self.add.len();
+        }
+        let copied_from = |ancestry: &[(Weak<String>, usize)], add: &Arc<String>| {
+            ancestry
+                .iter()
+                .filter(|(buffer, _)| std::ptr::eq(buffer.as_ptr(), Arc::as_ptr(add)))
+                .map(|&(_, length)| length)
+                .max()
+        };
+        let common_ancestor = self.add_ancestry.iter().flat_map(|(buffer, length)| {
+            other
+                .add_ancestry
+                .iter()
+                .filter(|(other_buffer, _)| Weak::ptr_eq(buffer, other_buffer))
+                .map(|(_, other_length)| (*length).min(*other_length))
+        });
+        copied_from(&other.add_ancestry, &self.add)
+            .into_iter()
+            .chain(copied_from(&self.add_ancestry, &other.add))
+            .chain(common_ancestor)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl PartialEq for Snapshot {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.pieces, &other.pieces) || same_text((&self.original, &self.add, &self.pieces), (&other.original, &other.add, &other.pieces))
+    }
+}
+
+impl Eq for Snapshot {}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl PieceTable {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            original: Arc::clone(&self.original),
+            add: Arc::clone(&self.add),
+            pieces: Arc::from(self.pieces.as_slice()),
+            length: self.length(),
+            add_ancestry: self.add_ancestry.clone(),
+        }
+    }
+
+    pub fn fork(&self) -> PieceTable {
+        PieceTable {
+            original: Arc::clone(&self.original),
+            add: Arc::clone(&self.add),
+            pieces: self.pieces.clone(),
+            piece_starts: self.piece_starts.clone(),
+            piece_lines: self.piece_lines.clone(),
+            length: self.length,
+            history: History::default(),
+            recording: None,
+            changes: self.changes.clone(),
+            line_breaks: self.line_breaks.clone(),
+            line_widths: self.line_widths.clone(),
+            insert_line_ending: self.insert_line_ending,
+            add_ancestry: self.add_ancestry.clone(),
+            observers: Observers::default(),
+            content_hash: self.content_hash.clone(),
+        }
+    }
+
+    pub fn split_off(&mut self, offset: usize) -> PieceTable {
+        let offset = offset.min(self.length());
+        let length = self.length() - offset;
+
+        let mut tail = Vec::new();
+        if length > 0 {
+            let change = self.replace_pieces(offset, length, Vec::new());
+            tail = change.removed.clone();
+            self.history.record(change);
+        }
+
+        let line_widths = LineWidths::new(tail.iter().map(|piece| self.piece_text(piece)));
+        let mut table = PieceTable {
+            original: Arc::clone(&self.original),
+            add: Arc::clone(&self.add),
+            pieces: tail,
+            piece_starts: Vec::new(),
+            piece_lines: Vec::new(),
+            length,
+            history: History::default(),
+            recording: None,
+            changes: ChangeLog::default(),
+            line_breaks: self.line_breaks.clone(),
+            line_widths,
+            insert_line_ending: self.insert_line_ending,
+            add_ancestry: self.add_ancestry.clone(),
+            observers: Observers::default(),
+            content_hash: HashCache::default(),
+        };
+        table.reindex_pieces(0);
+        table
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let pieces = if self.shares_buffers(snapshot) {
+            snapshot.pieces.to_vec()
+        } else {
+            vec![self.push_add(&snapshot.text())]
+        };
+
+        let length = self.length();
+        self.edit(0, length, pieces);
+    }
+
+    pub fn append_table(&mut self, other: PieceTable) {
+        if other.is_empty() {
+            return;
+        }
+
+        let share_original = Arc::ptr_eq(&self.original, &other.original);
+        let share_add = self.shares_add(&other.add);
+        let shared = |buffer: Buffer| match buffer {
+            Buffer::Original => share_original,
+            Buffer::Add => share_add,
+        };
+        let source = |buffer: Buffer| match buffer {
+            Buffer::Original => &*other.original,
+            Buffer::Add => other.add.as_str(),
+        };
+
+        // Only the spans the other table's pieces point into are copied, each overlapping run of
+        // them once, and the pieces are re-based onto the copies.
+        let mut spans: Vec<(Buffer, Range<usize>)> = other
+            .pieces
+            .iter()
+            .filter(|piece| !shared(piece.buffer))
+            .map(|piece| (piece.buffer, piece.offset..piece.offset + piece.length))
+            .collect();
+        spans.sort_by_key(|(buffer, span)| (*buffer, span.start));
+        let mut copies: Vec<(Buffer, Range<usize>, usize)> = Vec::new();
+        let mut copied = String::new();
+        for (buffer, span) in spans {
+            match copies.last_mut() {
+                Some((last_buffer, last, _)) if *last_buffer == buffer && span.start <= last.end => {
+                    if span.end > last.end {
+                        copied.push_str(&source(buffer)[last.end..span.end]);
+                        last.end = span.end;
+                    }
+                }
+                _ => {
+                    copies.push((buffer, span.clone(), copied.len()));
+                    copied.push_str(&source(buffer)[span]);
+                }
+            }
+        }
+        let base = if copied.is_empty() { 0 } else { self.push_add(&copied).offset };
+
+        let pieces: Vec<Piece> = other
+            .pieces
+            .iter()
+            .map(|&piece| {
+                if shared(piece.buffer) {
+                    return piece;
+                }
+                let index = copies.partition_point(|(buffer, span, _)| (*buffer, span.start) <= (piece.buffer, piece.offset)) - 1;
+                let (_, span, start) = &copies[index];
+                Piece::new(Buffer::Add, piece.length, base + start + piece.offset - span.start, piece.line_breaks)
+            })
+            .collect();
+
+        let length = self.length();
+        self.edit(length, 0, pieces);
+    }
+
+    fn shares_buffers(&self, snapshot: &Snapshot) -> bool {
+        self.shares_buffers_with(&snapshot.original, &snapshot.add)
+    }
+
+    fn shares_buffers_with(&self, original: &Arc<str>, add: &Arc<String>) -> bool {
+        Arc::ptr_eq(&self.original, original) && self.shares_add(add)
+    }
+
+    // Whether `add` is this table's add buffer or an earlier state of it, which it only appends to.
+    fn shares_add(&self, add: &Arc<String>) -> bool {
+        Arc::ptr_eq(&self.add, add)
+            || self
+                .add_ancestry
+                .iter()
+                .any(|(buffer, length)| std::ptr::eq(buffer.as_ptr(), Arc::as_ptr(add)) && add.len() <= *length)
+    }
+}
+
+type Spans<'a> = (&'a str, &'a str, &'a [Piece]);
+
+/// Compares two piece lists span by span without joining them. Spans that point at the very same
+/// bytes, as they do wherever two states of one table haven't diverged, are equal without reading
+/// them; only the divergent spans are compared byte by byte.
+pub(crate) fn same_text(left: Spans<'_>, right: Spans<'_>) -> bool {
+    let length = |pieces: &[Piece]| pieces.iter().map(|piece| piece.length).sum::<usize>();
+    if length(left.2) != length(right.2) {
+        return false;
+    }
+
+    let (mut lefts, mut rights) = (span_bytes(left), span_bytes(right));
+    let (mut left, mut right) = (lefts.next(), rights.next());
+    while let (Some(left_bytes), Some(right_bytes)) = (left, right) {
+        let length = left_bytes.len().min(right_bytes.len());
+        let (left_span, left_rest) = left_bytes.split_at(length);
+        let (right_span, right_rest) = right_bytes.split_at(length);
+        if !std::ptr::eq(left_span, right_span) && left_span != right_span {
+            return false;
+        }
+        left = if left_rest.is_empty() { lefts.next() } else { Some(left_rest) };
+        right = if right_rest.is_empty() { rights.next() } else { Some(right_rest) };
+    }
+    true
+}
+
+fn span_bytes<'a>((original, add, pieces): Spans<'a>) -> impl Iterator<Item = &'a [u8]> {
+    pieces
+        .iter()
+        .filter(|piece| piece.length > 0)
+        .map(move |piece| piece.text(original, add).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let mut piece_table = PieceTable::new("hello".to_string());
+        piece_table.insert(5, " world");
+
+        let snapshot = piece_table.snapshot();
+        let copy = snapshot.clone();
+        assert!(Arc::ptr_eq(&snapshot.add, &piece_table.add));
+
+        piece_table.delete(0, 6);
+        piece_table.insert(5, "!");
+        assert_eq!("world!", piece_table.text());
+        assert_eq!("hello world", snapshot.text());
+        assert_eq!(snapshot, copy);
+        assert_eq!(11, copy.length());
+        assert_eq!("hello world", copy.to_string());
+        assert!(!copy.is_empty());
+
+        piece_table.clear();
+        assert!(piece_table.snapshot().is_empty());
+        assert!(PieceTable::default().snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_eq() {
+        let mut piece_table = PieceTable::new("one two three".to_string());
+        piece_table.insert(3, ",");
+        let before = piece_table.snapshot();
+
+        piece_table.delete(9, 1);
+        piece_table.insert(9, "t");
+        piece_table.insert(0, "zero ");
+        piece_table.delete(0, 5);
+        assert!(!Arc::ptr_eq(&before.pieces, &piece_table.snapshot().pieces));
+        assert_eq!(before, piece_table.snapshot());
+
+        piece_table.replace(5..8, "TWO");
+        assert_ne!(before, piece_table.snapshot());
+        assert_eq!(PieceTable::from("one, TWO three").snapshot(), piece_table.snapshot());
+        assert_eq!(PieceTable::from("one, TWO three"), piece_table);
+        assert_ne!(PieceTable::from("one, TWO thre"), piece_table);
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut piece_table = PieceTable::new("shared".to_string());
+        piece_table.insert(6, " text");
+
+        let mut fork = piece_table.fork();
+        assert!(Arc::ptr_eq(&piece_table.add, &fork.add));
+        assert!(!fork.can_undo());
+
+        fork.insert(0, "forked ");
+        assert!(!Arc::ptr_eq(&piece_table.add, &fork.add));
+        assert!(Arc::ptr_eq(&piece_table.original, &fork.original));
+        assert_eq!("forked shared text", fork.text());
+        assert_eq!("shared text", piece_table.text());
+
+        piece_table.insert(0, "main ");
+        assert_eq!("main shared text", piece_table.text());
+        assert_eq!("forked shared text", fork.text());
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut piece_table = PieceTable::new("Subject: hi\n\nbody".to_string());
+        piece_table.insert(11, "!");
+
+        let mut body = piece_table.split_off(14);
+        assert_eq!("Subject: hi!\n\n", piece_table.text());
+        assert_eq!("body", body.text());
+        assert!(Arc::ptr_eq(&piece_table.original, &body.original));
+        assert!(Arc::ptr_eq(&piece_table.add, &body.add));
+        assert!(!body.can_undo());
+
+        body.insert(4, " text");
+        assert_eq!("body text", body.text());
+        assert!(piece_table.split_off(100).is_empty());
+        assert!(piece_table.undo());
+        assert_eq!("Subject: hi!\n\nbody", piece_table.text());
+    }
+
+    #[test]
+    fn test_append_table() {
+        let mut piece_table = PieceTable::new("header\nbody".to_string());
+        piece_table.insert(6, ":");
+        let mut body = piece_table.split_off(8);
+        piece_table.insert(0, "# ");
+
+        let add_length = piece_table.add.len();
+        piece_table.append_table(body.fork());
+        assert_eq!("# header:\nbody", piece_table.text());
+        assert_eq!(add_length, piece_table.add.len());
+
+        body.insert(4, " text");
+        let mut section = PieceTable::new("\nfooter".to_string());
+        section.insert(7, "!");
+        piece_table.append_table(body);
+        piece_table.append_table(section);
+        assert_eq!("# header:\nbodybody text\nfooter!", piece_table.text());
+
+        assert!(piece_table.undo());
+        assert_eq!("# header:\nbodybody text", piece_table.text());
+
+        let mut digits = PieceTable::new("0123456789".to_string());
+        digits.delete(0, 7);
+        digits.insert(0, "x");
+        digits.insert(2, "y");
+        let add_length = piece_table.add.len();
+        piece_table.append_table(digits);
+        assert_eq!("# header:\nbodybody textx7y89", piece_table.text());
+        assert_eq!(add_length + 5, piece_table.add.len());
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut piece_table = PieceTable::new("hello".to_string());
+        piece_table.insert(5, " world");
+        let snapshot = piece_table.snapshot();
+
+        piece_table.delete(0, 6);
+        piece_table.insert(0, "brave new ");
+        assert!(piece_table.shares_buffers(&snapshot));
+
+        piece_table.restore(&snapshot);
+        assert_eq!("hello world", piece_table.text());
+        assert!(piece_table.undo());
+        assert_eq!("brave new world", piece_table.text());
+
+        let other = PieceTable::new("other".to_string()).snapshot();
+        assert!(!piece_table.shares_buffers(&other));
+        piece_table.restore(&other);
+        assert_eq!("other", piece_table.text());
+    }
+}
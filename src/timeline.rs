@@ -0,0 +1,147 @@
+use crate::{NodeId, Piece, PieceTable};
+
+#[derive(Debug, Clone)]
+pub struct Revision<'a> {
+    table: &'a PieceTable,
+    node: NodeId,
+    pieces: Vec<Piece>,
+}
+
+impl<'a> Revision<'a> {
+    pub fn node(&self) -> NodeId {
+        self.node
+    }
+
+    pub fn length(&self) -> usize {
+        self.pieces.iter().map(|piece| piece.length).sum()
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.pieces.iter().map(|piece| self.table.piece_text(piece))
+    }
+
+    pub fn text(&self) -> String {
+        self.chunks().collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Revisions<'a> {
+    table: &'a PieceTable,
+    nodes: Vec<NodeId>,
+    front: usize,
+    back: usize,
+    front_pieces: Vec<Piece>,
+    back_pieces: Vec<Piece>,
+}
+
+impl<'a> Iterator for Revisions<'a> {
+    type Item = Revision<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+
+        let revision = self.revision(self.front, self.front_pieces.clone());
+        self.front += 1;
+        if self.front < self.back {
+            self.table
+                .history
+                .step(self.nodes[self.front])
+                .apply_to(&mut self.front_pieces, &self.table.line_breaks);
+        }
+        Some(revision)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back - self.front;
+        (remaining, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for Revisions<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        let revision = self.revision(self.back, self.back_pieces.clone());
+        if self.back > self.front {
+            self.table
+                .history
+                .step(self.nodes[self.back])
+                .revert_from(&mut self.back_pieces, &self.table.line_breaks);
+        }
+        Some(revision)
+    }
+}
+
+impl ExactSizeIterator for Revisions<'_> {}
+
+impl<'a> Revisions<'a> {
+    fn revision(&self, index: usize, pieces: Vec<Piece>) -> Revision<'a> {
+        Revision {
+            table: self.table,
+            node: self.nodes[index],
+            pieces,
+        }
+    }
+}
+
+impl PieceTable {
+    pub fn revisions(&self) -> Revisions<'_> {
+        let history = &self.history;
+        let (nodes, position) = history.timeline();
+
+        let mut current = self.pieces.clone();
+        history.pending().revert_from(&mut current, &self.line_breaks);
+
+        let mut front_pieces = current.clone();
+        for &node in nodes[1..=position].iter().rev() {
+            history.step(node).revert_from(&mut front_pieces, &self.line_breaks);
+        }
+        let mut back_pieces = current;
+        for &node in &nodes[position + 1..] {
+            history.step(node).apply_to(&mut back_pieces, &self.line_breaks);
+        }
+
+        Revisions {
+            table: self,
+            back: nodes.len(),
+            nodes,
+            front: 0,
+            front_pieces,
+            back_pieces,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revisions() {
+        let mut piece_table = PieceTable::new("a".to_string());
+        piece_table.insert(1, "b");
+        piece_table.insert(2, "c");
+        piece_table.delete(0, 1);
+        piece_table.undo();
+
+        let forward: Vec<String> = piece_table.revisions().map(|revision| revision.text()).collect();
+        assert_eq!(vec!["a", "ab", "abc", "bc"], forward);
+
+        let backward: Vec<String> = piece_table.revisions().rev().map(|revision| revision.text()).collect();
+        assert_eq!(vec!["bc", "abc", "ab", "a"], backward);
+
+        let mut revisions = piece_table.revisions();
+        assert_eq!(4, revisions.len());
+        assert_eq!("a", revisions.next().unwrap().text());
+        assert_eq!("bc", revisions.next_back().unwrap().text());
+        assert_eq!(piece_table.current_node(), revisions.next_back().unwrap().node());
+        assert_eq!(2, revisions.next().unwrap().length());
+        assert!(revisions.next().is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::PieceTable;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct LineWidths {
+    widths: Vec<usize>,
+    counts: BTreeMap<usize, usize>,
+}
+
+impl LineWidths {
+    pub(crate) fn new<'a>(chunks: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut line_widths = LineWidths::default();
+        line_widths.splice(0..0, widths(chunks));
+        line_widths
+    }
+
+    fn splice(&mut self, lines: Range<usize>, widths: Vec<usize>) {
+        for &width in &widths {
+            *self.counts.entry(width).or_default() += 1;
+        }
+        for width in self.widths.splice(lines, widths) {
+            if let Some(count) = self.counts.get_mut(&width) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(&width);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the width of each line in `chunks`, which hold the lines in full. A '\r' only belongs
+/// to the terminator if a '\n' follows it, maybe in the next chunk.
+fn widths<'a>(chunks: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let mut widths = Vec::new();
+    let mut width = 0;
+    let mut after_cr = false;
+    for ch in chunks.into_iter().flat_map(str::chars) {
+        if ch == '\n' {
+            widths.push(width - usize::from(after_cr));
+            width = 0;
+        } else {
+            width += 1;
+        }
+        after_cr = ch == '\r';
+    }
+    widths.push(width);
+    widths
+}
+
+impl PieceTable {
+    /// Width in chars of the longest line, excluding terminators.
+    pub fn max_line_width(&self) -> usize {
+        self.line_widths.counts.last_key_value().map_or(0, |(&width, _)| width)
+    }
+
+    pub fn line_width(&self, line: usize) -> Option<usize> {
+        self.line_widths.widths.get(line).copied()
+    }
+
+    pub(crate) fn track_line_widths(&mut self, offset: usize, removed_breaks: usize, inserted_breaks: usize) {
+        let first = self.byte_to_line(offset);
+        let start = self.line_to_byte(first);
+        let end = self.line_range(first + inserted_breaks).end;
+        let widths = widths(self.chunks_in_range(start..end));
+        self.line_widths.splice(first..first + removed_breaks + 1, widths);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_widths(piece_table: &PieceTable) {
+        let expected: Vec<usize> = (0..piece_table.line_count())
+            .map(|line| piece_table.line(line).unwrap().chars().count())
+            .collect();
+        assert_eq!(expected, piece_table.line_widths.widths);
+        assert_eq!(expected.iter().copied().max().unwrap_or(0), piece_table.max_line_width());
+    }
+
+    #[test]
+    fn test_max_line_width() {
+        let mut piece_table = PieceTable::from("short\r\nthe longest line\nmid line");
+        assert_eq!(16, piece_table.max_line_width());
+        assert_eq!(Some(5), piece_table.line_width(0));
+
+        piece_table.delete(7, 12);
+        assert_eq!(8, piece_table.max_line_width());
+        piece_table.insert(6, "ünïcode\r");
+        piece_table.insert(5, "X");
+        piece_table.push_str("\n\nend\r");
+        piece_table.delete(4, 4);
+        assert_widths(&piece_table);
+
+        piece_table.undo();
+        piece_table.undo();
+        assert_widths(&piece_table);
+        let tail = piece_table.split_off(9);
+        assert_widths(&piece_table);
+        assert_widths(&tail);
+
+        let mut split_crlf = PieceTable::from("\nb");
+        split_crlf.insert(0, "a\r");
+        assert_eq!(Some(1), split_crlf.line_width(0));
+        assert_widths(&split_crlf);
+    }
+}
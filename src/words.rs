@@ -0,0 +1,153 @@
+use crate::PieceTable;
+use std::borrow::Cow;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Segmentation {
+    WordBounds,
+    Sentences,
+}
+
+impl Segmentation {
+    // Where the first segment of `text` ends, and whether another segment starts after that
+    // one. The boundary only depends on the text up to the next one, so once a third segment
+    // starts, text appended to the window can no longer move it.
+    fn first_end(self, text: &str) -> (Option<usize>, bool) {
+        fn after_first(mut starts: impl Iterator<Item = usize>) -> (Option<usize>, bool) {
+            starts.next();
+            (starts.next(), starts.next().is_some())
+        }
+        match self {
+            Segmentation::WordBounds => after_first(text.split_word_bound_indices().map(|(start, _)| start)),
+            Segmentation::Sentences => after_first(text.split_sentence_bound_indices().map(|(start, _)| start)),
+        }
+    }
+}
+
+/// Segments the text lazily from an offset known to be a boundary, over a window that starts as
+/// the rest of the current piece and only grows into the next pieces while the end of the first
+/// segment is unsettled.
+#[derive(Debug, Clone)]
+struct Segments<'a> {
+    table: &'a PieceTable,
+    offset: usize,
+    segmentation: Segmentation,
+}
+
+impl Iterator for Segments<'_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let length = self.table.length();
+        if self.offset >= length {
+            return None;
+        }
+
+        let start = self.offset;
+        let (chunk, chunk_start) = self.table.chunk_at(start);
+        let mut window = Cow::Borrowed(&chunk[start - chunk_start..]);
+        let mut window_end = chunk_start + chunk.len();
+        loop {
+            let (end, settled) = self.segmentation.first_end(&window);
+            if settled || window_end == length {
+                self.offset = start + end.unwrap_or(window.len());
+                return Some(start..self.offset);
+            }
+            let (next, _) = self.table.chunk_at(window_end);
+            window.to_mut().push_str(next);
+            window_end += next.len();
+        }
+    }
+}
+
+impl PieceTable {
+    pub fn words(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.words_from(0)
+    }
+
+    pub fn word_bounds(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.segments_from(0, Segmentation::WordBounds)
+    }
+
+    pub fn sentences(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.segments_from(0, Segmentation::Sentences)
+    }
+
+    pub fn next_word_boundary(&self, offset: usize) -> usize {
+        let line_start = self.line_to_byte(self.byte_to_line(offset));
+        self.words_from(line_start)
+            .map(|word| word.end)
+            .find(|&end| end > offset)
+            .unwrap_or(self.length())
+    }
+
+    pub fn prev_word_boundary(&self, offset: usize) -> usize {
+        // Walks back a line at a time until one has a word starting before `offset`.
+        let mut line = self.byte_to_line(offset);
+        loop {
+            let line_start = self.line_to_byte(line);
+            let found = self
+                .words_from(line_start)
+                .map(|word| word.start)
+                .take_while(|&start| start < offset)
+                .last();
+            match found {
+                Some(start) => return start,
+                None if line == 0 => return 0,
+                None => line -= 1,
+            }
+        }
+    }
+
+    // `offset` must be a word boundary, such as the start of a line.
+    fn words_from(&self, offset: usize) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.segments_from(offset, Segmentation::WordBounds)
+            .filter(|bound| self.chunks_in_range(bound.clone()).flat_map(str::chars).any(char::is_alphanumeric))
+    }
+
+    fn segments_from(&self, offset: usize, segmentation: Segmentation) -> Segments<'_> {
+        Segments {
+            table: self,
+            offset,
+            segmentation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words() {
+        let mut piece_table = PieceTable::from("The quick (\"brown\") fox");
+        piece_table.insert(10, "fox's ");
+        assert_eq!("The quick fox's (\"brown\") fox", piece_table.text());
+
+        let words: Vec<String> = piece_table.words().map(|word| piece_table.slice(word)).collect();
+        assert_eq!(vec!["The", "quick", "fox's", "brown", "fox"], words);
+        assert_eq!(Some(3..4), piece_table.word_bounds().nth(1));
+
+        assert_eq!(9, piece_table.next_word_boundary(3));
+        assert_eq!(15, piece_table.next_word_boundary(10));
+        assert_eq!(29, piece_table.next_word_boundary(28));
+        assert_eq!(18, piece_table.prev_word_boundary(23));
+        assert_eq!(0, piece_table.prev_word_boundary(4));
+        assert_eq!(29, piece_table.next_word_boundary(29));
+
+        let mut split = PieceTable::from("3 a");
+        split.insert(1, ".5");
+        assert_eq!(vec![0..3, 3..4, 4..5], split.word_bounds().collect::<Vec<_>>());
+        assert_eq!(3, split.next_word_boundary(1));
+    }
+
+    #[test]
+    fn test_sentences() {
+        let mut piece_table = PieceTable::from("Hello there. It is 3.5 degrees!");
+        piece_table.insert(31, " Really?");
+
+        let sentences: Vec<String> = piece_table.sentences().map(|sentence| piece_table.slice(sentence)).collect();
+        assert_eq!(vec!["Hello there. ", "It is 3.5 degrees! ", "Really?"], sentences);
+    }
+}
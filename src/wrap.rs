@@ -0,0 +1,64 @@
+use crate::PieceTable;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WrapMode {
+    Chars,
+    Words,
+}
+
+impl PieceTable {
+    /// Returns the offsets at which line `line` continues on a new visual row when rows hold at
+    /// most `max_width` chars. In `Words` mode rows break after whitespace where possible, and
+    /// whitespace at the end of a full row hangs past the edge instead of starting the next one.
+    pub fn wrap_points(&self, line: usize, max_width: usize, mode: WrapMode) -> Vec<usize> {
+        let range = self.line_range(line);
+        let mut points = Vec::new();
+        if max_width == 0 {
+            return points;
+        }
+
+        let mut offset = range.start;
+        let mut width = 0;
+        let mut last_space: Option<(usize, usize)> = None;
+        for chunk in self.chunks_in_range(range) {
+            for ch in chunk.chars() {
+                let hangs = mode == WrapMode::Words && ch.is_whitespace();
+                if width >= max_width && !hangs {
+                    match last_space.take() {
+                        Some((after, row_width)) => {
+                            points.push(after);
+                            width -= row_width;
+                        }
+                        None => {
+                            points.push(offset);
+                            width = 0;
+                        }
+                    }
+                }
+                width += 1;
+                offset += ch.len_utf8();
+                if hangs {
+                    last_space = Some((offset, width));
+                }
+            }
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_points() {
+        let mut piece_table = PieceTable::from("first\nhello world foo  barbazquux\n");
+        piece_table.insert(17, "é");
+
+        assert_eq!(vec![16, 27], piece_table.wrap_points(1, 10, WrapMode::Chars));
+        assert_eq!(vec![12, 25], piece_table.wrap_points(1, 10, WrapMode::Words));
+        assert_eq!(Vec::<usize>::new(), piece_table.wrap_points(0, 10, WrapMode::Words));
+        assert_eq!(Vec::<usize>::new(), piece_table.wrap_points(1, 0, WrapMode::Chars));
+    }
+}
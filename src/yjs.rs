@@ -0,0 +1,140 @@
+use crate::{Edit, PieceTable};
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use yrs::types::text::TextEvent;
+use yrs::types::{Delta, Observable};
+use yrs::updates::decoder::Decode;
+use yrs::{Doc, GetString, Out, Text, TextRef, Transact, Update};
+
+#[derive(Debug)]
+pub enum YjsError {
+    Decode(yrs::encoding::read::Error),
+    Update(yrs::error::UpdateError),
+}
+
+impl Display for YjsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            YjsError::Decode(error) => write!(f, "failed to decode yjs update: {error}"),
+            YjsError::Update(error) => write!(f, "failed to apply yjs update: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for YjsError {}
+
+pub struct YjsText {
+    doc: Doc,
+    text: TextRef,
+    received: Arc<Mutex<Vec<Edit>>>,
+}
+
+impl YjsText {
+    pub fn new(doc: Doc, name: &str) -> Self {
+        let text = doc.get_or_insert_text(name);
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let sink = Arc::clone(&received);
+        text.observe("piece_table", move |txn, event: &TextEvent| {
+            let mut edits = sink.lock().expect("yjs observer lock");
+            let mut position = 0;
+            for delta in event.delta(txn) {
+                match delta {
+                    Delta::Retain(length, _) => position += *length as usize,
+                    Delta::Deleted(length) => edits.push(Edit::Delete {
+                        range: position..position + *length as usize,
+                    }),
+                    Delta::Inserted(value, _) => {
+                        let text = match value {
+                            Out::Any(any) => any.to_string(),
+                            other => other.clone().to_string(txn),
+                        };
+                        position += text.len();
+                        edits.push(Edit::Insert {
+                            offset: position - text.len(),
+                            text,
+                        });
+                    }
+                }
+            }
+        });
+
+        YjsText { doc, text, received }
+    }
+
+    pub fn doc(&self) -> &Doc {
+        &self.doc
+    }
+
+    pub fn table(&self) -> PieceTable {
+        PieceTable::new(self.text.get_string(&self.doc.transact()))
+    }
+
+    pub fn apply_update(&mut self, table: &mut PieceTable, update: &[u8]) -> Result<(), YjsError> {
+        let update = Update::decode_v1(update).map_err(YjsError::Decode)?;
+        self.doc.transact_mut().apply_update(update).map_err(YjsError::Update)?;
+
+        let edits = std::mem::take(&mut *self.received.lock().expect("yjs observer lock"));
+        table.transaction(|table| {
+            for edit in &edits {
+                table.apply_edit(edit);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn apply_local(&mut self, table: &mut PieceTable, edit: &Edit) -> Vec<u8> {
+        table.apply_edit(edit);
+
+        let update = {
+            let mut txn = self.doc.transact_mut();
+            match edit {
+                Edit::Insert { offset, text } => self.text.insert(&mut txn, *offset as u32, text),
+                Edit::Delete { range } => self.text.remove_range(&mut txn, range.start as u32, range.len() as u32),
+                Edit::Replace { range, text } => {
+                    self.text.remove_range(&mut txn, range.start as u32, range.len() as u32);
+                    self.text.insert(&mut txn, range.start as u32, text);
+                }
+            }
+            txn.encode_update_v1()
+        };
+        self.received.lock().expect("yjs observer lock").clear();
+        update
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yjs_round_trip() {
+        let mut local = YjsText::new(Doc::new(), "content");
+        let mut remote = YjsText::new(Doc::new(), "content");
+        let mut local_table = local.table();
+        let mut remote_table = remote.table();
+
+        let first = local.apply_local(
+            &mut local_table,
+            &Edit::Insert {
+                offset: 0,
+                text: "hello wörld".to_string(),
+            },
+        );
+        let second = local.apply_local(&mut local_table, &Edit::Delete { range: 0..6 });
+        remote.apply_update(&mut remote_table, &first).unwrap();
+        remote.apply_update(&mut remote_table, &second).unwrap();
+        assert_eq!("wörld", remote_table.text());
+
+        let reply = remote.apply_local(
+            &mut remote_table,
+            &Edit::Insert {
+                offset: 6,
+                text: "!".to_string(),
+            },
+        );
+        local.apply_update(&mut local_table, &reply).unwrap();
+        assert_eq!("wörld!", local_table.text());
+        assert!(remote.apply_update(&mut remote_table, &[0xff]).is_err());
+    }
+}